@@ -17,32 +17,20 @@ fn main() {
 	)
 	.expect("Cannot open file");
 
-	let mut nodes = 0usize;
-	let mut edges = Vec::<(usize, usize)>::new();
+	let mut edges = Vec::<(String, String)>::new();
 	for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
 		let line = line.expect("Error reading CSV");
 		let mut columns = line.split(&[' ', '\t', ',', ';'][..]);
 		if let (Some(n1), Some(n2)) = (columns.next(), columns.skip_while(|&c| c.is_empty()).next())
 		{
-			if let (Ok(n1), Ok(n2)) = (n1.parse(), n2.parse()) {
-				if n1 > nodes {
-					nodes = n1;
-				}
-				if n2 > nodes {
-					nodes = n2;
-				}
-				edges.push((n1, n2));
-			} else {
-				eprintln!("Ignored line {} has bad number format", i);
-			}
+			edges.push((n1.to_string(), n2.to_string()));
 		} else {
 			eprintln!("Ignored line {} has <2 columns", i);
 		}
 	}
 
-	let mut layout = Layout::<f64>::from_graph(
+	let mut layout = KeyedLayout::<f64, String>::from_graph(
 		edges,
-		nodes + 1,
 		Settings {
 			dimensions: 2,
 			dissuade_hubs: false,
@@ -54,8 +42,10 @@ fn main() {
 			prevent_overlapping: None,
 			strong_gravity: false,
 			barnes_hut: None,
+			..Default::default()
 		},
-	);
+	)
+	.expect("Cannot build layout");
 
 	eprintln!("Computing layout...");
 	for i in 0..ITERATIONS {
@@ -59,6 +59,11 @@ fn project(v: [f32; 3], r: f32) -> [f32; 3] {
 	[v[0] * f, v[1] * f, v[2] * f]
 }
 
+/// Rendered radius of a node
+fn node_radius(degree: usize) -> f32 {
+	NODE_RADIUS * degree as f32
+}
+
 fn ico_sphere(r: f32, iterations: usize) -> Vec<Triangle> {
 	let mut sphere = octahedron(r);
 	let mut sphere2 = Vec::<Triangle>::new();
@@ -258,18 +263,24 @@ fn main() {
 			kg: 10000.0, //1.0
 			kr: 100.0,   //0.1
 			lin_log: false,
-			prevent_overlapping: Some((NODE_RADIUS as f64, 100.0)),
+			prevent_overlapping: Some(100.0),
 			strong_gravity: false,
 			barnes_hut: Some(0.5),
+			..Default::default()
 		},
-	);
+	)
+	.expect("Cannot build layout");
+	// Same radii as the rendered spheres, in layout units
+	for (node, degree) in layout.nodes.iter_mut().zip(degrees.iter()) {
+		node.size = (node_radius(*degree) / SCALE) as f64;
+	}
 
 	for _ in 0..ITERATIONS {
 		layout.iteration();
 	}
 
 	// -------- Generate Solid
-	let sphere = ico_sphere(NODE_RADIUS, 2);
+	let sphere = ico_sphere(1.0, 2);
 	//let cylinder = cylinder(EDGE_RADIUS, 1.0, 6);
 	let mut solid = Vec::with_capacity(
 		sphere.len() * nb_nodes, /* + cylinder.len() * edges.len()*/
@@ -277,7 +288,7 @@ fn main() {
 
 	for (i, node) in layout.points.iter().enumerate() {
 		let mut new_sphere = clone_solid(&sphere);
-		uniscale(&mut new_sphere, degrees[i] as f32);
+		uniscale(&mut new_sphere, node_radius(degrees[i]));
 		translate(
 			&mut new_sphere,
 			dbg!([
@@ -36,8 +36,10 @@ fn main() {
 			prevent_overlapping: None,
 			strong_gravity: false,
 			barnes_hut: None,
+			..Default::default()
 		},
-	);
+	)
+	.expect("Cannot build layout");
 
 	eprintln!("Computing layout...");
 	for i in 0..ITERATIONS {
@@ -0,0 +1,379 @@
+use crate::util::{self, Coord, Node, PointList, Position};
+
+/// Maximum depth of the tree: deeper nodes share their leaf (they are almost superposed)
+const MAX_DEPTH: usize = 32;
+
+/// What a node interacts with
+pub enum Body<'a, T> {
+	/// Another node (its index)
+	Node(usize),
+	/// Distant nodes, approximated by their total mass at their center of mass
+	Cluster(&'a ClusterMass<T>),
+}
+
+/// Mass of the nodes of a cell
+pub struct ClusterMass<T> {
+	pub total: T,
+	/// Mass of the nodes having a group, by group (sorted by group)
+	groups: Vec<(usize, T)>,
+	/// Sum of `groups`
+	grouped: T,
+}
+
+impl<T: Coord> ClusterMass<T> {
+	fn zero() -> Self {
+		Self {
+			total: T::zero(),
+			groups: Vec::new(),
+			grouped: T::zero(),
+		}
+	}
+
+	/// Adds the mass of a node
+	fn add(&mut self, mass: &T, group: Option<usize>) {
+		self.total += mass.clone();
+		if let Some(group) = group {
+			self.grouped += mass.clone();
+			match self.groups.binary_search_by_key(&group, |(g, _m)| *g) {
+				Ok(i) => self.groups[i].1 += mass.clone(),
+				Err(i) => self.groups.insert(i, (group, mass.clone())),
+			}
+		}
+	}
+
+	/// Adds the mass of a sub-cell
+	fn merge(&mut self, other: &Self) {
+		self.total += other.total.clone();
+		if other.groups.is_empty() {
+			return;
+		}
+		self.grouped += other.grouped.clone();
+		let mut groups = Vec::with_capacity(self.groups.len() + other.groups.len());
+		let (mut mine, mut theirs) = (
+			std::mem::take(&mut self.groups).into_iter().peekable(),
+			other.groups.iter().peekable(),
+		);
+		loop {
+			let next = match (mine.peek(), theirs.peek()) {
+				(Some((g1, _)), Some((g2, _))) if g1 == g2 => {
+					let (group, mass) = mine.next().unwrap();
+					(group, mass + theirs.next().unwrap().1.clone())
+				}
+				(Some((g1, _)), Some((g2, _))) if g1 > g2 => theirs.next().unwrap().clone(),
+				(Some(_), _) => mine.next().unwrap(),
+				(None, Some(_)) => theirs.next().unwrap().clone(),
+				(None, None) => break,
+			};
+			groups.push(next);
+		}
+		self.groups = groups;
+	}
+
+	/// Mass seen by a node of group `group`, the mass of the other groups being multiplied by `inter_group`
+	pub fn seen_by(&self, group: Option<usize>, inter_group: &T) -> T {
+		let group = match group {
+			Some(group) if !self.groups.is_empty() => group,
+			_ => return self.total.clone(),
+		};
+		let own = match self.groups.binary_search_by_key(&group, |(g, _m)| *g) {
+			Ok(i) => self.groups[i].1.clone(),
+			Err(_) => T::zero(),
+		};
+		self.total.clone() + (inter_group.clone() - T::one()) * (self.grouped.clone() - own)
+	}
+}
+
+/// Hypercube containing nodes
+struct Cell<T> {
+	center: Vec<T>,
+	half_width: T,
+	depth: usize,
+	mass: ClusterMass<T>,
+	mass_center: Vec<T>,
+	/// Indices of the sub-cells (at most `2^dimensions`)
+	children: Vec<usize>,
+	/// Nodes contained by a leaf
+	bodies: Vec<usize>,
+}
+
+impl<T: Coord> Cell<T> {
+	fn new(center: Vec<T>, half_width: T, depth: usize) -> Self {
+		Self {
+			mass_center: center.iter().map(|_| T::zero()).collect(),
+			center,
+			half_width,
+			depth,
+			mass: ClusterMass::zero(),
+			children: Vec::new(),
+			bodies: Vec::new(),
+		}
+	}
+
+	/// Whether `pos` is in the sub-cell `child` (assuming `pos` is in this cell)
+	#[inline]
+	fn is_in_child(&self, child: &Self, pos: &Position<T>) -> bool {
+		pos.iter()
+			.zip(self.center.iter())
+			.zip(child.center.iter())
+			.all(|((x, center), child_center)| (x >= center) == (child_center > center))
+	}
+
+	/// Whether sub-cells would still be distinguishable with `T`'s precision
+	fn can_split(&self) -> bool {
+		let quarter_width = self.half_width.clone() / T::from(2.0);
+		self.center.iter().all(|center| {
+			center.clone() + quarter_width.clone() != *center
+				&& center.clone() - quarter_width.clone() != *center
+		})
+	}
+
+	#[inline]
+	fn contains(&self, pos: &Position<T>) -> bool {
+		pos.iter().zip(self.center.iter()).all(|(x, center)| {
+			let d = x.clone() - center.clone();
+			d <= self.half_width && -d <= self.half_width
+		})
+	}
+}
+
+/// Barnes-Hut tree in any number of dimensions
+///
+/// Each cell is split into `2^dimensions` sub-cells (quadtree in 2D, octree in 3D, etc.).
+/// Only non-empty sub-cells are allocated.
+pub struct Tree<T> {
+	cells: Vec<Cell<T>>,
+}
+
+impl<T: Coord> Tree<T> {
+	/// Builds the tree of the nodes `bodies` (must not be empty)
+	pub fn new(points: &PointList<T>, nodes: &[Node<T>], bodies: &[usize]) -> Self {
+		let mut min = points.get_clone(bodies[0]);
+		let mut max = min.clone();
+		for pos in bodies.iter().map(|n| points.get(*n)) {
+			for i in 0usize..points.dimensions {
+				if pos[i] < min[i] {
+					min[i] = pos[i].clone();
+				}
+				if pos[i] > max[i] {
+					max[i] = pos[i].clone();
+				}
+			}
+		}
+		let mut half_width = T::zero();
+		let center = min
+			.into_iter()
+			.zip(max)
+			.map(|(min, max)| {
+				half_width = util::max(
+					half_width.clone(),
+					(max.clone() - min.clone()) / T::from(2.0),
+				);
+				(min + max) / T::from(2.0)
+			})
+			.collect();
+		if half_width.is_zero() {
+			half_width = T::one();
+		}
+
+		let mut tree = Self {
+			cells: vec![Cell::new(center, half_width, 0)],
+		};
+		for n in bodies.iter() {
+			tree.insert(points, *n);
+		}
+		tree.compute_masses(points, nodes);
+		tree
+	}
+
+	fn insert(&mut self, points: &PointList<T>, n: usize) {
+		let pos = points.get(n);
+		let mut c = 0;
+		loop {
+			let cell = &mut self.cells[c];
+			if cell.children.is_empty() {
+				if cell.bodies.is_empty() || cell.depth >= MAX_DEPTH || !cell.can_split() {
+					cell.bodies.push(n);
+					return;
+				}
+				// Split the leaf
+				for other in std::mem::take(&mut cell.bodies) {
+					let child = self.child(c, points.get(other));
+					self.cells[child].bodies.push(other);
+				}
+			}
+			c = self.child(c, pos);
+		}
+	}
+
+	/// Returns the sub-cell of `c` containing `pos`, creating it if needed
+	fn child(&mut self, c: usize, pos: &Position<T>) -> usize {
+		let cell = &self.cells[c];
+		if let Some(child) = cell
+			.children
+			.iter()
+			.find(|child| cell.is_in_child(&self.cells[**child], pos))
+		{
+			return *child;
+		}
+		let half_width = cell.half_width.clone() / T::from(2.0);
+		let center = pos
+			.iter()
+			.zip(cell.center.iter())
+			.map(|(x, center)| {
+				if x >= center {
+					center.clone() + half_width.clone()
+				} else {
+					center.clone() - half_width.clone()
+				}
+			})
+			.collect();
+		let child = Cell::new(center, half_width, cell.depth + 1);
+		let index = self.cells.len();
+		self.cells.push(child);
+		self.cells[c].children.push(index);
+		index
+	}
+
+	fn compute_masses(&mut self, points: &PointList<T>, nodes: &[Node<T>]) {
+		// Sub-cells are always after their parent
+		for c in (0..self.cells.len()).rev() {
+			let cell = &self.cells[c];
+			let mut mass = ClusterMass::zero();
+			let mut mass_center: Vec<T> = cell.center.iter().map(|_| T::zero()).collect();
+			for n in cell.bodies.iter() {
+				let node = &nodes[*n];
+				mass.add(&node.mass, node.group);
+				for (x, y) in mass_center.iter_mut().zip(points.get(*n).iter()) {
+					*x += node.mass.clone() * y.clone();
+				}
+			}
+			for child in cell.children.iter() {
+				let child = &self.cells[*child];
+				mass.merge(&child.mass);
+				for (x, y) in mass_center.iter_mut().zip(child.mass_center.iter()) {
+					*x += child.mass.total.clone() * y.clone();
+				}
+			}
+			if mass.total.is_zero() {
+				mass_center = cell.center.clone();
+			} else {
+				for x in mass_center.iter_mut() {
+					*x /= mass.total.clone();
+				}
+			}
+			let cell = &mut self.cells[c];
+			cell.mass = mass;
+			cell.mass_center = mass_center;
+		}
+	}
+
+	/// Calls `f(body, di, d2)` for every body interacting with node `n`
+	///
+	/// `di` is the vector from `n` to the body, and `d2` its squared norm.
+	/// A cell is approximated as a cluster when its width is less than `theta` times its distance.
+	pub fn for_each_interaction<F: FnMut(Body<T>, &[T], T)>(
+		&self,
+		points: &PointList<T>,
+		n: usize,
+		theta: &T,
+		mut f: F,
+	) {
+		let pos = points.get(n);
+		let theta2 = theta.clone().powi(2);
+		let mut di = points.get_clone(n);
+		let mut stack = vec![0usize];
+		while let Some(c) = stack.pop() {
+			let cell = &self.cells[c];
+			if cell.children.is_empty() {
+				for other in cell.bodies.iter() {
+					if *other == n {
+						continue;
+					}
+					let mut d2 = T::zero();
+					for (i, x) in points.get(*other).iter().enumerate() {
+						di[i] = x.clone() - pos[i].clone();
+						d2 += di[i].clone().powi(2);
+					}
+					f(Body::Node(*other), &di, d2);
+				}
+				continue;
+			}
+			let mut d2 = T::zero();
+			for (i, x) in cell.mass_center.iter().enumerate() {
+				di[i] = x.clone() - pos[i].clone();
+				d2 += di[i].clone().powi(2);
+			}
+			let width2 = (cell.half_width.clone() * T::from(2.0)).powi(2);
+			if width2 < theta2.clone() * d2.clone() && !cell.contains(pos) {
+				f(Body::Cluster(&cell.mass), &di, d2);
+			} else {
+				stack.extend_from_slice(&cell.children);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_tree() {
+		let points = PointList {
+			dimensions: 3,
+			points: vec![
+				0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0,
+				1.0, 1.0,
+			],
+		};
+		let nodes: Vec<Node<f32>> = (0..6)
+			.map(|_| Node {
+				degree: 0,
+				mass: 1.0,
+				size: 0.0,
+				fixed: false,
+				group: None,
+			})
+			.collect();
+		let tree = Tree::new(&points, &nodes, &[0, 1, 2, 3, 4, 5]);
+		assert_eq!(tree.cells[0].mass.total, 6.0);
+		assert_eq!(tree.cells[0].mass_center, vec![0.5, 0.5, 0.5]);
+		// Superposed nodes end up in the same leaf
+		assert!(tree.cells.iter().any(|cell| cell.bodies == vec![4, 5]));
+
+		// theta = 0: exact interactions with every other node
+		let mut others = Vec::new();
+		tree.for_each_interaction(&points, 0, &0.0, |body, _di, _d2| match body {
+			Body::Node(other) => others.push(other),
+			Body::Cluster(_) => panic!("unexpected cluster"),
+		});
+		others.sort_unstable();
+		assert_eq!(others, vec![1, 2, 3, 4, 5]);
+
+		// Large theta: the far corner is a cluster
+		let mut clusters = 0.0;
+		tree.for_each_interaction(&points, 0, &1.0, |body, _di, _d2| {
+			if let Body::Cluster(mass) = body {
+				clusters += mass.total;
+			}
+		});
+		assert_eq!(clusters, 2.0);
+	}
+
+	#[test]
+	fn test_cluster_mass() {
+		let mut mass = ClusterMass::zero();
+		mass.add(&1.0, Some(2));
+		mass.add(&2.0, None);
+		let mut other = ClusterMass::zero();
+		other.add(&3.0, Some(0));
+		other.add(&4.0, Some(2));
+		mass.merge(&other);
+		assert_eq!(mass.total, 10.0);
+		assert_eq!(mass.groups, vec![(0, 3.0), (2, 5.0)]);
+		assert_eq!(mass.seen_by(None, &2.0), 10.0);
+		assert_eq!(mass.seen_by(Some(0), &2.0), 15.0);
+		assert_eq!(mass.seen_by(Some(1), &2.0), 18.0);
+		assert_eq!(mass.seen_by(Some(2), &1.0), 10.0);
+	}
+}
@@ -0,0 +1,109 @@
+use std::fmt;
+
+/// Error returned when building or modifying a layout
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+	/// A node does not exist
+	NodeIndex {
+		/// Index of the edge referring to the node, if any
+		edge: Option<usize>,
+		/// Index of the node
+		node: usize,
+		/// Number of nodes
+		nb_nodes: usize,
+	},
+	/// An edge refers to a key having no position
+	UnknownKey { edge: usize },
+	/// An edge does not exist
+	EdgeIndex { edge: usize, nb_edges: usize },
+	/// A position does not have `settings.dimensions` coordinates
+	DimensionMismatch {
+		/// Index of the node
+		node: usize,
+		expected: usize,
+		found: usize,
+	},
+	/// `settings.dimensions` is zero
+	ZeroDimensions,
+	/// The graph has no node
+	EmptyGraph,
+	/// The number of weights is not the number of edges
+	WeightCount { expected: usize, found: usize },
+	/// An edge weight is negative, infinite or NaN
+	InvalidWeight { edge: usize },
+	/// The number of edge types is not the number of edges
+	EdgeTypeCount { expected: usize, found: usize },
+	/// An edge type is not in `Settings::edge_types`
+	UnknownEdgeType { edge: usize, edge_type: usize },
+	/// The layout cannot go from its current number of dimensions to the requested one
+	/// (a projection cannot add dimensions, and a lifting cannot remove them)
+	DimensionChange { current: usize, requested: usize },
+	/// A setting has an invalid value (the message tells which one)
+	InvalidSettings(&'static str),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Error::NodeIndex {
+				edge: Some(edge),
+				node,
+				nb_nodes,
+			} => write!(
+				f,
+				"edge {} refers to node {} but there are {} nodes",
+				edge, node, nb_nodes
+			),
+			Error::NodeIndex {
+				edge: None,
+				node,
+				nb_nodes,
+			} => write!(
+				f,
+				"node {} does not exist, there are {} nodes",
+				node, nb_nodes
+			),
+			Error::UnknownKey { edge } => {
+				write!(f, "edge {} refers to a key without position", edge)
+			}
+			Error::EdgeIndex { edge, nb_edges } => write!(
+				f,
+				"edge {} does not exist, there are {} edges",
+				edge, nb_edges
+			),
+			Error::DimensionMismatch {
+				node,
+				expected,
+				found,
+			} => write!(
+				f,
+				"position of node {} has {} coordinates instead of {}",
+				node, found, expected
+			),
+			Error::ZeroDimensions => write!(f, "the number of dimensions must not be zero"),
+			Error::EmptyGraph => write!(f, "the graph has no node"),
+			Error::WeightCount { expected, found } => {
+				write!(f, "expected {} edge weights, found {}", expected, found)
+			}
+			Error::InvalidWeight { edge } => {
+				write!(f, "edge {} has a negative or non-finite weight", edge)
+			}
+			Error::EdgeTypeCount { expected, found } => {
+				write!(f, "expected {} edge types, found {}", expected, found)
+			}
+			Error::UnknownEdgeType { edge, edge_type } => write!(
+				f,
+				"edge {} has type {} which is not in the settings",
+				edge, edge_type
+			),
+			Error::DimensionChange { current, requested } => write!(
+				f,
+				"cannot change the layout from {} to {} dimensions this way",
+				current, requested
+			),
+			Error::InvalidSettings(msg) => write!(f, "invalid settings: {}", msg),
+		}
+	}
+}
+
+impl std::error::Error for Error {}
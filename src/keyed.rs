@@ -0,0 +1,715 @@
+use crate::{
+	check_weight, placement, util, ConvergenceReport, Coord, Edge, EdgeList, Error, Layout, Node,
+	Position, Settings, Tolerance,
+};
+
+use std::{
+	collections::{HashMap, VecDeque},
+	hash::Hash,
+	ops::Deref,
+};
+
+/// Bidirectional map between node keys and node indices
+#[derive(Clone)]
+pub struct IdMap<K> {
+	keys: Vec<K>,
+	indices: HashMap<K, usize>,
+}
+
+impl<K: Clone + Eq + Hash> IdMap<K> {
+	pub fn new() -> Self {
+		Self {
+			keys: Vec::new(),
+			indices: HashMap::new(),
+		}
+	}
+
+	/// Index of a key
+	pub fn index(&self, key: &K) -> Option<usize> {
+		self.indices.get(key).copied()
+	}
+
+	/// Key of an index
+	pub fn key(&self, n: usize) -> Option<&K> {
+		self.keys.get(n)
+	}
+
+	/// Keys, in the order of the indices
+	pub fn keys(&self) -> &[K] {
+		&self.keys
+	}
+
+	pub fn len(&self) -> usize {
+		self.keys.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.keys.is_empty()
+	}
+
+	/// Returns the index of `key`, giving it a fresh index if it is new
+	pub fn insert(&mut self, key: K) -> usize {
+		if let Some(n) = self.indices.get(&key) {
+			return *n;
+		}
+		let n = self.keys.len();
+		self.indices.insert(key.clone(), n);
+		self.keys.push(key);
+		n
+	}
+
+	/// Removes the key of index `n`, the last key taking its index (as in `Vec::swap_remove`)
+	fn swap_remove(&mut self, n: usize) {
+		let key = self.keys.swap_remove(n);
+		self.indices.remove(&key);
+		if let Some(moved) = self.keys.get(n) {
+			self.indices.insert(moved.clone(), n);
+		}
+	}
+}
+
+impl<K: Clone + Eq + Hash> Default for IdMap<K> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+/// Layout of a graph whose nodes are identified by keys of any type
+///
+/// Dereferences to the underlying [`Layout`], whose nodes are indexed as in [`KeyedLayout::ids`].
+/// It is only mutable through this wrapper, so that the keys stay consistent with the nodes.
+pub struct KeyedLayout<T: Coord, K> {
+	pub(crate) layout: Layout<T>,
+	pub(crate) ids: IdMap<K>,
+}
+
+impl<'a, T: Coord + std::fmt::Debug, K: Clone + Eq + Hash> KeyedLayout<T, K> {
+	/// Instanciates a randomly positioned layout from a directed graph
+	///
+	/// Nodes are indexed in order of first appearance in `edges`.
+	#[cfg(feature = "rand")]
+	pub fn from_graph(edges: Vec<(K, K)>, settings: Settings<T>) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_weighted(edges.into(), settings)
+	}
+
+	/// Same as [`KeyedLayout::from_graph`], with edge weights (see [`EdgeList`])
+	#[cfg(feature = "rand")]
+	pub fn from_graph_weighted(
+		graph: EdgeList<T, (K, K)>,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_with_rng_weighted(graph, settings, &mut rand::thread_rng())
+	}
+
+	/// Same as [`KeyedLayout::from_graph`], with positions drawn from `rng`
+	#[cfg(feature = "rand")]
+	pub fn from_graph_with_rng<R: rand::Rng>(
+		edges: Vec<(K, K)>,
+		settings: Settings<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_with_rng_weighted(edges.into(), settings, rng)
+	}
+
+	/// Same as [`KeyedLayout::from_graph_with_rng`], with edge weights (see [`EdgeList`])
+	#[cfg(feature = "rand")]
+	pub fn from_graph_with_rng_weighted<R: rand::Rng>(
+		graph: EdgeList<T, (K, K)>,
+		settings: Settings<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let (ids, graph) = index_edges(graph);
+		Ok(Self {
+			layout: Layout::from_graph_with_rng_weighted(graph, ids.len(), settings, rng)?,
+			ids,
+		})
+	}
+
+	/// Instanciates a quasi-randomly positioned layout from a directed graph (see [`Layout::from_graph_halton`])
+	///
+	/// Nodes are indexed in order of first appearance in `edges`.
+	pub fn from_graph_halton(
+		edges: Vec<(K, K)>,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error> {
+		Self::from_graph_halton_weighted(edges.into(), settings, seed)
+	}
+
+	/// Same as [`KeyedLayout::from_graph_halton`], with edge weights (see [`EdgeList`])
+	pub fn from_graph_halton_weighted(
+		graph: EdgeList<T, (K, K)>,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error> {
+		let (ids, graph) = index_edges(graph);
+		Ok(Self {
+			layout: Layout::from_graph_halton_weighted(graph, ids.len(), settings, seed)?,
+			ids,
+		})
+	}
+
+	/// Instanciates layout from a directed graph, using initial positions
+	///
+	/// Nodes are indexed in the order of `nodes` (a duplicate key keeps its first position).
+	/// Every key in `edges` must have a position.
+	pub fn from_position_graph<I: IntoIterator<Item = (K, &'a Position<T>)>>(
+		edges: Vec<(K, K)>,
+		nodes: I,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
+	where
+		T: 'a,
+	{
+		Self::from_position_graph_weighted(edges.into(), nodes, settings)
+	}
+
+	/// Same as [`KeyedLayout::from_position_graph`], with edge weights (see [`EdgeList`])
+	pub fn from_position_graph_weighted<I: IntoIterator<Item = (K, &'a Position<T>)>>(
+		graph: EdgeList<T, (K, K)>,
+		nodes: I,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
+	where
+		T: 'a,
+	{
+		let mut ids = IdMap::new();
+		let mut positions = Vec::new();
+		for (key, pos) in nodes {
+			if ids.insert(key) == positions.len() {
+				positions.push(pos);
+			}
+		}
+		let EdgeList {
+			edges,
+			weights,
+			types,
+		} = graph;
+		let edges = edges
+			.iter()
+			.enumerate()
+			.map(|(e, (k1, k2))| match (ids.index(k1), ids.index(k2)) {
+				(Some(n1), Some(n2)) => Ok((n1, n2)),
+				_ => Err(Error::UnknownKey { edge: e }),
+			})
+			.collect::<Result<Vec<Edge>, Error>>()?;
+		Ok(Self {
+			layout: Layout::from_position_graph_weighted(
+				EdgeList {
+					edges,
+					weights,
+					types,
+				},
+				positions.into_iter(),
+				settings,
+			)?,
+			ids,
+		})
+	}
+
+	/// Instanciates a layout from a directed graph, starting from the positions of a previous layout of the same network
+	///
+	/// Nodes are indexed in order of first appearance in `edges`. Nodes whose key is in `previous` start at their previous position,
+	/// and with their previous speed if `keep_speeds` (the global speed is then kept too).
+	/// Other nodes are placed near their placed neighbors (see [`KeyedLayout::from_position_map`]).
+	pub fn from_previous(
+		edges: Vec<(K, K)>,
+		previous: &KeyedLayout<T, K>,
+		settings: Settings<T>,
+		keep_speeds: bool,
+	) -> Result<Self, Error> {
+		Self::from_previous_weighted(edges.into(), previous, settings, keep_speeds)
+	}
+
+	/// Same as [`KeyedLayout::from_previous`], with edge weights (see [`EdgeList`])
+	pub fn from_previous_weighted(
+		graph: EdgeList<T, (K, K)>,
+		previous: &KeyedLayout<T, K>,
+		settings: Settings<T>,
+		keep_speeds: bool,
+	) -> Result<Self, Error> {
+		let (ids, graph) = index_edges(graph);
+		let known = ids
+			.keys()
+			.iter()
+			.map(|key| previous.position(key))
+			.collect();
+		let mut layout = Self::warm_start(ids, graph, known, settings)?;
+		if keep_speeds {
+			for (n, key) in layout.ids.keys.iter().enumerate() {
+				if let Some(m) = previous.ids.index(key) {
+					layout
+						.layout
+						.speeds
+						.get_mut(n)
+						.clone_from_slice(previous.speeds.get(m));
+					layout
+						.layout
+						.old_speeds
+						.get_mut(n)
+						.clone_from_slice(previous.old_speeds.get(m));
+				}
+			}
+			layout.layout.speed = previous.speed.clone();
+			layout.layout.speed_efficiency = previous.speed_efficiency.clone();
+		}
+		Ok(layout)
+	}
+
+	/// Instanciates a layout from a directed graph, starting from saved positions (e.g. from [`KeyedLayout::position_map`])
+	///
+	/// Nodes are indexed in order of first appearance in `edges`. Nodes whose key is in `positions` start there.
+	/// The other ones are placed at the centroid of their placed neighbors, spreading from the known nodes;
+	/// those in components without any known node start around the centroid of the known nodes.
+	pub fn from_position_map(
+		edges: Vec<(K, K)>,
+		positions: &HashMap<K, Vec<T>>,
+		settings: Settings<T>,
+	) -> Result<Self, Error> {
+		Self::from_position_map_weighted(edges.into(), positions, settings)
+	}
+
+	/// Same as [`KeyedLayout::from_position_map`], with edge weights (see [`EdgeList`])
+	pub fn from_position_map_weighted(
+		graph: EdgeList<T, (K, K)>,
+		positions: &HashMap<K, Vec<T>>,
+		settings: Settings<T>,
+	) -> Result<Self, Error> {
+		let (ids, graph) = index_edges(graph);
+		let known = ids
+			.keys()
+			.iter()
+			.map(|key| positions.get(key).map(|pos| &pos[..]))
+			.collect();
+		Self::warm_start(ids, graph, known, settings)
+	}
+
+	/// `known[n]` is the starting position of the node `n`, if any
+	fn warm_start(
+		ids: IdMap<K>,
+		graph: EdgeList<T>,
+		known: Vec<Option<&Position<T>>>,
+		settings: Settings<T>,
+	) -> Result<Self, Error> {
+		let points = place_unknown(settings.dimensions, &graph.edges, known);
+		Ok(Self {
+			layout: Layout::from_position_graph_weighted(
+				graph,
+				points.iter().map(|pos| &pos[..]),
+				settings,
+			)?,
+			ids,
+		})
+	}
+
+	pub fn ids(&self) -> &IdMap<K> {
+		&self.ids
+	}
+
+	/// Position of a node
+	pub fn position(&self, key: &K) -> Option<&Position<T>> {
+		self.ids.index(key).map(|n| self.layout.points.get(n))
+	}
+
+	/// Keys and positions of the nodes
+	pub fn positions(&self) -> impl Iterator<Item = (&K, &Position<T>)> {
+		self.ids.keys.iter().zip(self.layout.points.iter())
+	}
+
+	/// Positions of the nodes, by key
+	pub fn position_map(&self) -> HashMap<K, Vec<T>> {
+		self.positions()
+			.map(|(key, pos)| (key.clone(), pos.to_vec()))
+			.collect()
+	}
+
+	/// Mutable position of a node
+	pub fn position_mut(&mut self, key: &K) -> Option<&mut Position<T>> {
+		let n = self.ids.index(key)?;
+		Some(self.layout.points.get_mut(n))
+	}
+
+	/// Mutable node (size, mass, fixed, group)
+	pub fn node_mut(&mut self, key: &K) -> Option<&mut Node<T>> {
+		let n = self.ids.index(key)?;
+		self.layout.nodes.get_mut(n)
+	}
+
+	/// Mutable nodes, indexed as in [`KeyedLayout::ids`]
+	pub fn nodes_mut(&mut self) -> &mut [Node<T>] {
+		&mut self.layout.nodes
+	}
+
+	/// Mutable settings (see [`Layout::settings`])
+	pub fn settings_mut(&mut self) -> &mut Settings<T> {
+		&mut self.layout.settings
+	}
+
+	/// See [`Layout::update_masses`]
+	pub fn update_masses(&mut self) {
+		self.layout.update_masses()
+	}
+
+	/// See [`Layout::set_edge_types`]
+	pub fn set_edge_types(&mut self, edge_types: Vec<Option<usize>>) -> Result<(), Error> {
+		self.layout.set_edge_types(edge_types)
+	}
+
+	/// See [`Layout::set_edge_type`]
+	pub fn set_edge_type(&mut self, e: usize, edge_type: Option<usize>) -> Result<(), Error> {
+		self.layout.set_edge_type(e, edge_type)
+	}
+
+	/// Computes an iteration of ForceAtlas2
+	pub fn iteration(&mut self) {
+		self.layout.iteration()
+	}
+
+	/// See [`Layout::run_until_converged`]
+	pub fn run_until_converged(
+		&mut self,
+		tolerance: &Tolerance<T>,
+		max_iterations: usize,
+	) -> ConvergenceReport<T> {
+		self.layout.run_until_converged(tolerance, max_iterations)
+	}
+
+	/// See [`Layout::project`]
+	pub fn project(&mut self, dimensions: usize) -> Result<(), Error> {
+		self.layout.project(dimensions)
+	}
+
+	/// See [`Layout::lift`]
+	#[cfg(feature = "rand")]
+	pub fn lift<R: rand::Rng>(&mut self, dimensions: usize, rng: &mut R) -> Result<(), Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		self.layout.lift(dimensions, rng)
+	}
+
+	/// Adds a node linked to `neighbors`, returning its index
+	///
+	/// New keys (`key` or neighbors) get fresh indices; a new node is placed at the centroid of its known neighbours.
+	/// Edges are `(key, neighbor)`, with weight `1`.
+	pub fn add_node(&mut self, key: K, neighbors: &[K]) -> usize {
+		let known: Vec<usize> = neighbors
+			.iter()
+			.filter_map(|neighbor| self.ids.index(neighbor))
+			.collect();
+		let n = self.index_or_push(key, &known);
+		for neighbor in neighbors.iter() {
+			let m = self.index_or_push(neighbor.clone(), &[n]);
+			self.layout.link_edge((n, m), None);
+		}
+		self.layout.update_custom_masses();
+		n
+	}
+
+	/// Removes a node and its edges, returning whether it existed
+	///
+	/// The last node takes the index of the removed one.
+	/// A layout cannot be empty, so removing its only node fails with `Error::EmptyGraph`.
+	pub fn remove_node(&mut self, key: &K) -> Result<bool, Error> {
+		match self.ids.index(key) {
+			Some(n) => {
+				self.layout.remove_node(n)?;
+				self.ids.swap_remove(n);
+				Ok(true)
+			}
+			None => Ok(false),
+		}
+	}
+
+	/// Adds an edge, returning its index
+	///
+	/// New keys get fresh indices, and are placed next to the other end of the edge.
+	/// `weight` defaults to `1`, and must be finite and non-negative.
+	pub fn add_edge(&mut self, (k1, k2): (K, K), weight: Option<T>) -> Result<usize, Error> {
+		let e = self.layout.edges.len();
+		if let Some(weight) = &weight {
+			check_weight(e, weight)?;
+		}
+		let known: Vec<usize> = self.ids.index(&k2).into_iter().collect();
+		let n1 = self.index_or_push(k1, &known);
+		let n2 = self.index_or_push(k2, &[n1]);
+		self.layout.link_edge((n1, n2), weight);
+		self.layout.update_custom_masses();
+		Ok(e)
+	}
+
+	/// Removes the first edge `(k1, k2)`, returning whether it existed
+	pub fn remove_edge(&mut self, k1: &K, k2: &K) -> bool {
+		let edge = match (self.ids.index(k1), self.ids.index(k2)) {
+			(Some(n1), Some(n2)) => (n1, n2),
+			_ => return false,
+		};
+		match self.layout.edges.iter().position(|e| *e == edge) {
+			Some(e) => {
+				self.layout
+					.remove_edge(e)
+					.expect("IdMap and Layout are inconsistent");
+				true
+			}
+			None => false,
+		}
+	}
+
+	fn index_or_push(&mut self, key: K, neighbors: &[usize]) -> usize {
+		match self.ids.index(&key) {
+			Some(n) => n,
+			None => {
+				self.layout.push_node(neighbors);
+				self.ids.insert(key)
+			}
+		}
+	}
+}
+
+/// Indexes nodes in order of first appearance
+fn index_edges<T, K: Clone + Eq + Hash>(graph: EdgeList<T, (K, K)>) -> (IdMap<K>, EdgeList<T>) {
+	let mut ids = IdMap::new();
+	let edges = graph
+		.edges
+		.into_iter()
+		.map(|(k1, k2)| (ids.insert(k1), ids.insert(k2)))
+		.collect();
+	(
+		ids,
+		EdgeList {
+			edges,
+			weights: graph.weights,
+			types: graph.types,
+		},
+	)
+}
+
+/// Positions of the nodes, placing the ones without a known position at the centroid of their placed neighbors
+///
+/// Known positions are kept as is (even with wrong dimensions, so that they are reported).
+/// Placement spreads breadth-first from the known nodes; components without known nodes start at the centroid of the known nodes.
+/// Placed nodes are shifted by a small deterministic offset (a hundredth of the mean length of the edges between known nodes),
+/// so that nodes with the same neighbors are not superposed.
+/// Runs in `O((nodes + edges) * dimensions)`.
+fn place_unknown<T: Coord>(
+	dimensions: usize,
+	edges: &[Edge],
+	known: Vec<Option<&Position<T>>>,
+) -> Vec<Vec<T>> {
+	let nb_nodes = known.len();
+	let neighbors = placement::neighbor_lists(nb_nodes, edges);
+	let mut points: Vec<Option<Vec<T>>> = known
+		.into_iter()
+		.map(|pos| pos.map(|pos| pos.to_vec()))
+		.collect();
+	let scale = util::mean_edge_length(edges, |n| points[n].as_deref()).unwrap_or_else(T::one)
+		/ T::from(100.0);
+	let shift = |pos: &mut [T], n: usize| {
+		for (d, x) in pos.iter_mut().enumerate() {
+			*x += scale.clone() * T::from(placement::tie_break(n, d) as f32);
+		}
+	};
+
+	// Centroid of the known nodes (or origin)
+	let mut origin: Vec<T> = (0..dimensions).map(|_| T::zero()).collect();
+	let mut count = 0usize;
+	for pos in points.iter().flatten() {
+		for (x, y) in origin.iter_mut().zip(pos.iter()) {
+			*x += y.clone();
+		}
+		count += 1;
+	}
+	if count > 0 {
+		let count = T::from(count as f32);
+		origin.iter_mut().for_each(|x| *x /= count.clone());
+	}
+
+	let mut queue: VecDeque<usize> = (0..nb_nodes).filter(|n| points[*n].is_some()).collect();
+	let mut seed = 0;
+	loop {
+		// Spread from the placed nodes
+		while let Some(n) = queue.pop_front() {
+			for m in neighbors[n].iter() {
+				if points[*m].is_some() {
+					continue;
+				}
+				let mut pos: Vec<T> = (0..dimensions).map(|_| T::zero()).collect();
+				let mut count = 0usize;
+				for other in neighbors[*m].iter().filter_map(|o| points[*o].as_ref()) {
+					for (x, y) in pos.iter_mut().zip(other.iter()) {
+						*x += y.clone();
+					}
+					count += 1;
+				}
+				let count = T::from(count as f32);
+				pos.iter_mut().for_each(|x| *x /= count.clone());
+				shift(&mut pos, *m);
+				points[*m] = Some(pos);
+				queue.push_back(*m);
+			}
+		}
+		// Seed the next component without placed nodes
+		while seed < nb_nodes && points[seed].is_some() {
+			seed += 1;
+		}
+		if seed == nb_nodes {
+			break;
+		}
+		let mut pos = origin.clone();
+		shift(&mut pos, seed);
+		points[seed] = Some(pos);
+		queue.push_back(seed);
+	}
+	points.into_iter().map(|pos| pos.unwrap()).collect()
+}
+
+impl<T: Coord, K> Deref for KeyedLayout<T, K> {
+	type Target = Layout<T>;
+
+	fn deref(&self) -> &Self::Target {
+		&self.layout
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_keyed_layout() {
+		let mut layout = KeyedLayout::<f64, &str>::from_position_graph(
+			vec![("a", "b"), ("b", "c")],
+			vec![
+				("a", &[0.0, 0.0][..]),
+				("b", &[2.0, 0.0][..]),
+				("c", &[2.0, 2.0][..]),
+			],
+			Settings::default(),
+		)
+		.unwrap();
+		assert_eq!(layout.ids().index(&"c"), Some(2));
+		assert_eq!(layout.position(&"b"), Some(&[2.0, 0.0][..]));
+		assert_eq!(layout.position(&"z"), None);
+
+		assert_eq!(layout.add_node("d", &["a", "c"]), 3);
+		assert_eq!(layout.position(&"d"), Some(&[1.0, 1.0][..]));
+		assert_eq!(layout.add_edge(("e", "a"), None), Ok(4));
+		assert_eq!(
+			layout.add_edge(("f", "a"), Some(-1.0)),
+			Err(Error::InvalidWeight { edge: 5 })
+		);
+		assert_eq!(layout.ids().index(&"f"), None);
+		assert_eq!(layout.ids().key(4), Some(&"e"));
+		assert_eq!(layout.edges[4], (4, 0));
+
+		assert_eq!(layout.remove_node(&"a"), Ok(true));
+		assert_eq!(layout.remove_node(&"a"), Ok(false));
+		assert_eq!(layout.ids().index(&"e"), Some(0));
+		assert_eq!(layout.ids().len(), layout.nodes.len());
+		assert_eq!(layout.edges, vec![(1, 2), (3, 2)]);
+		assert!(layout.remove_edge(&"b", &"c"));
+		assert!(!layout.remove_edge(&"b", &"c"));
+		assert_eq!(
+			layout
+				.positions()
+				.map(|(key, _pos)| *key)
+				.collect::<Vec<&str>>(),
+			vec!["e", "b", "c", "d"]
+		);
+		layout.position_mut(&"d").unwrap()[0] = 3.0;
+		assert_eq!(layout.position(&"d"), Some(&[3.0, 1.0][..]));
+		layout.node_mut(&"d").unwrap().fixed = true;
+		assert!(layout.node_mut(&"z").is_none());
+		layout.iteration();
+		assert_eq!(layout.position(&"d"), Some(&[3.0, 1.0][..]));
+
+		// Warm start: "b" and "c" were removed, "f" is new
+		let previous = layout;
+		let mut layout = KeyedLayout::<f64, &str>::from_previous(
+			vec![("e", "d"), ("d", "f"), ("g", "h")],
+			&previous,
+			Settings::default(),
+			true,
+		)
+		.unwrap();
+		assert_eq!(layout.position(&"e"), previous.position(&"e"));
+		assert_eq!(layout.position(&"d"), previous.position(&"d"));
+		assert_eq!(layout.speeds.get(0), previous.speeds.get(0));
+		// Placed next to "d"
+		let dist = |layout: &KeyedLayout<f64, &str>, k1, k2| {
+			let (p1, p2) = (layout.position(&k1).unwrap(), layout.position(&k2).unwrap());
+			p1.iter()
+				.zip(p2.iter())
+				.map(|(x1, x2)| (x1 - x2).powi(2))
+				.sum::<f64>()
+				.sqrt()
+		};
+		let length = dist(&layout, "e", "d");
+		assert!(dist(&layout, "f", "d") > 0.0 && dist(&layout, "f", "d") < 0.02 * length);
+		assert!(dist(&layout, "g", "h") > 0.0);
+		layout.iteration();
+
+		let mut positions = layout.position_map();
+		positions.insert("z", vec![0.0]);
+		let layout = KeyedLayout::<f64, &str>::from_position_map(
+			vec![("e", "d"), ("d", "x"), ("x", "y")],
+			&positions,
+			Settings::default(),
+		)
+		.unwrap();
+		assert_eq!(layout.position(&"e").unwrap(), &positions[&"e"][..]);
+		let length = dist(&layout, "e", "d");
+		assert!(dist(&layout, "x", "d") < 0.02 * length && dist(&layout, "y", "x") < 0.02 * length);
+
+		// The offset of nodes sharing their neighbours scales with the layout
+		let layout = KeyedLayout::<f64, &str>::from_position_map(
+			vec![("a", "b"), ("x", "a"), ("x", "b"), ("y", "a"), ("y", "b")],
+			&vec![("a", vec![0.0, 0.0]), ("b", vec![300.0, 0.0])]
+				.into_iter()
+				.collect(),
+			Settings::default(),
+		)
+		.unwrap();
+		assert!(dist(&layout, "x", "y") > 0.3 && dist(&layout, "x", "y") < 6.0);
+		assert_eq!(
+			KeyedLayout::<f64, &str>::from_position_map(
+				vec![("a", "z")],
+				&positions,
+				Settings::default(),
+			)
+			.err(),
+			Some(Error::DimensionMismatch {
+				node: 1,
+				expected: 2,
+				found: 1
+			})
+		);
+
+		assert_eq!(
+			KeyedLayout::<f64, &str>::from_position_graph(
+				vec![("a", "b")],
+				vec![("a", &[0.0, 0.0][..])],
+				Settings::default(),
+			)
+			.err(),
+			Some(Error::UnknownKey { edge: 0 })
+		);
+	}
+}
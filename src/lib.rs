@@ -1,12 +1,176 @@
-#![feature(specialization)]
-#![feature(trait_alias)]
-#![allow(incomplete_features)]
-
+#[cfg(feature = "barnes_hut")]
+mod barnes_hut;
+mod error;
+mod keyed;
+#[cfg(feature = "rand")]
+mod multilevel;
+#[cfg(feature = "petgraph")]
+mod petgraph_ext;
+mod placement;
+mod projection;
 mod util;
 
+pub use error::Error;
+pub use keyed::{IdMap, KeyedLayout};
+#[cfg(feature = "rand")]
+pub use multilevel::Multilevel;
+pub use placement::{InitialPlacement, PlacementFn};
 pub use util::{Coord, Edge, Node, PointIter, PointIterMut, PointList, Position};
 
 use itertools::izip;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use std::{
+	collections::{hash_map::Entry, HashMap},
+	sync::Arc,
+};
+
+/// Function of a node's index and of the node, giving its mass (see [`Mass::Custom`])
+pub type MassFn<T> = Arc<dyn Fn(usize, &Node<T>) -> T + Send + Sync>;
+
+/// Mass of the nodes, used by repulsion, gravity and displacement
+#[derive(Clone)]
+pub enum Mass<T> {
+	/// `degree + 1`, as in Gephi
+	Degree,
+	/// `weighted_degree + 1` (sum of the raw weights of the node's edges)
+	WeightedDegree,
+	/// `in_degree + 1` (number of edges `(_, n)`)
+	InDegree,
+	/// Function of the node's index and of the node
+	Custom(MassFn<T>),
+}
+
+/// Edges of a graph, with optional data in the same order as `edges`
+///
+/// Taken by the `*_weighted` constructors. A plain `Vec` of edges converts into an `EdgeList` without data.
+#[derive(Clone, Debug)]
+pub struct EdgeList<T, E = Edge> {
+	pub edges: Vec<E>,
+	/// Edge weights (`None` means all weights are `1`)
+	pub weights: Option<Vec<T>>,
+	/// Edge types (`None` means no edge is typed, see [`Layout::set_edge_types`])
+	pub types: Option<Vec<Option<usize>>>,
+}
+
+impl<T, E> Default for EdgeList<T, E> {
+	fn default() -> Self {
+		Self {
+			edges: Vec::new(),
+			weights: None,
+			types: None,
+		}
+	}
+}
+
+impl<T, E> From<Vec<E>> for EdgeList<T, E> {
+	fn from(edges: Vec<E>) -> Self {
+		Self {
+			edges,
+			weights: None,
+			types: None,
+		}
+	}
+}
+
+/// Cleaning of the graph at construction
+#[derive(Clone, Default)]
+pub struct Normalization {
+	/// Remove the edges `(n, n)`
+	pub remove_self_loops: bool,
+	/// Merge duplicate edges into one edge, weighted by their total weight
+	///
+	/// Without weights, the weight of the merged edge is its multiplicity. Edges of different types are not duplicates.
+	pub merge_duplicates: bool,
+	/// `(a, b)` and `(b, a)` are duplicates (only used with `merge_duplicates`)
+	pub undirected: bool,
+}
+
+impl Normalization {
+	fn normalize<T: Coord>(&self, graph: EdgeList<T>) -> EdgeList<T> {
+		if !self.remove_self_loops && !self.merge_duplicates {
+			return graph;
+		}
+		let EdgeList {
+			edges,
+			weights,
+			types,
+		} = graph;
+		let mut kept_edges = Vec::with_capacity(edges.len());
+		let mut kept_weights: Vec<T> = Vec::with_capacity(edges.len());
+		let mut kept_types = Vec::with_capacity(edges.len());
+		let mut merged = false;
+		let mut indices: HashMap<(Edge, Option<usize>), usize> = HashMap::new();
+		for (e, (n1, n2)) in edges.into_iter().enumerate() {
+			if self.remove_self_loops && n1 == n2 {
+				continue;
+			}
+			let w = weights.as_ref().map_or_else(T::one, |w| w[e].clone());
+			let edge_type = types.as_ref().and_then(|types| types[e]);
+			if self.merge_duplicates {
+				let key = if self.undirected && n2 < n1 {
+					((n2, n1), edge_type)
+				} else {
+					((n1, n2), edge_type)
+				};
+				match indices.entry(key) {
+					Entry::Occupied(index) => {
+						kept_weights[*index.get()] += w;
+						merged = true;
+						continue;
+					}
+					Entry::Vacant(index) => {
+						index.insert(kept_edges.len());
+					}
+				}
+			}
+			kept_edges.push((n1, n2));
+			kept_weights.push(w);
+			kept_types.push(edge_type);
+		}
+		EdgeList {
+			edges: kept_edges,
+			weights: if weights.is_some() || merged {
+				Some(kept_weights)
+			} else {
+				None
+			},
+			types: types.map(|_| kept_types),
+		}
+	}
+}
+
+/// Attraction settings of a type of edges (see [`Layout::set_edge_types`])
+#[derive(Clone)]
+pub struct EdgeType<T> {
+	/// Attraction coefficient
+	pub ka: T,
+	/// Logarithmic attraction
+	pub lin_log: bool,
+}
+
+/// Checks that an edge type is `None` or in `settings.edge_types`
+fn check_edge_type<T: Coord>(
+	settings: &Settings<T>,
+	e: usize,
+	edge_type: Option<usize>,
+) -> Result<(), Error> {
+	match edge_type {
+		Some(edge_type) if edge_type >= settings.edge_types.len() => {
+			Err(Error::UnknownEdgeType { edge: e, edge_type })
+		}
+		_ => Ok(()),
+	}
+}
+
+/// Checks that an edge weight is finite and non-negative
+fn check_weight<T: Coord>(e: usize, weight: &T) -> Result<(), Error> {
+	if weight.is_finite() && !weight.negative() {
+		Ok(())
+	} else {
+		Err(Error::InvalidWeight { edge: e })
+	}
+}
 
 #[derive(Clone)]
 pub struct Settings<T: Coord> {
@@ -16,25 +180,50 @@ pub struct Settings<T: Coord> {
 	pub dissuade_hubs: bool,
 	/// Attraction coefficient
 	pub ka: T,
+	/// Exponent applied to edge weights in attraction, as in Gephi
+	///
+	/// `0` ignores weights, `1` uses them linearly.
+	pub edge_weight_influence: T,
 	/// Gravity coefficient
 	pub kg: T,
 	/// Repulsion coefficient
 	pub kr: T,
 	/// Logarithmic attraction
 	pub lin_log: bool,
+	/// Attraction settings of each edge type, replacing `ka` and `lin_log` for typed edges
+	pub edge_types: Vec<EdgeType<T>>,
 	/// Scaling of the force before displacement (small is slow)
 	pub scaling_ratio: T,
-	/// Prevent node overlapping for a prettier graph (node_size, kr_prime).
+	/// Tolerance to swinging when adjusting the global speed (high is fast but imprecise)
+	pub jitter_tolerance: T,
+	/// Maximum ratio by which the global speed can increase at each iteration
+	pub max_speed_ratio: T,
+	/// How the nodes' masses are computed
+	pub mass: Mass<T>,
+	/// Cleaning of the graph, applied at construction
+	pub normalization: Normalization,
+	/// Prevent node overlapping for a prettier graph (kr_prime).
 	///
-	/// `node_size` is the radius around a node where the repulsion coefficient is `kr_prime`.
+	/// Each node is a ball of radius `Node::size`. The repulsion coefficient of two overlapping nodes is `kr_prime`.
 	/// `kr_prime` is arbitrarily set to `100.0` in Gephi implementation.
-	pub prevent_overlapping: Option<(T, T)>,
+	pub prevent_overlapping: Option<T>,
 	/// Gravity does not decrease with distance, resulting in a more compact graph.
 	pub strong_gravity: bool,
+	/// Attraction multiplier for the edges inside a group (see `Node::group`)
+	pub intra_group_attraction: T,
+	/// Attraction multiplier for the edges between different groups
+	pub inter_group_attraction: T,
+	/// Repulsion multiplier between nodes of different groups
+	pub inter_group_repulsion: T,
+	/// Lay out connected components independently, and pack them without overlapping.
+	/// The argument is the margin between components.
+	///
+	/// Nodes of different components do not repulse each other, and gravity pulls each component to its own center.
+	/// After each iteration, the components' bounding boxes are packed in rows (on the first two dimensions).
+	/// Components containing a fixed node are not moved, and the others are packed beside them.
+	pub pack_components: Option<T>,
 	/// Optimize repulsion using Barnes-Hut algorithm (time passes from N^2 to NlogN).
 	/// The argument is theta.
-	///
-	/// **Note**: only implemented for `T=f64` and `dimension` 2 or 3.
 	#[cfg(feature = "barnes_hut")]
 	pub barnes_hut: Option<T>,
 }
@@ -45,659 +234,2005 @@ impl<T: Coord> Default for Settings<T> {
 			dimensions: 2,
 			dissuade_hubs: false,
 			ka: T::from(0.5),
+			edge_weight_influence: T::one(),
 			kg: T::one(),
 			kr: T::one(),
 			scaling_ratio: T::one(),
+			jitter_tolerance: T::one(),
+			max_speed_ratio: T::from(0.5),
 			lin_log: false,
+			edge_types: Vec::new(),
 			prevent_overlapping: None,
 			strong_gravity: false,
+			intra_group_attraction: T::one(),
+			inter_group_attraction: T::one(),
+			inter_group_repulsion: T::one(),
+			pack_components: None,
+			mass: Mass::Degree,
+			normalization: Normalization::default(),
 			#[cfg(feature = "barnes_hut")]
 			barnes_hut: None,
 		}
 	}
 }
 
+impl<T: Coord> Settings<T> {
+	/// Checks that the settings are usable
+	pub fn check(&self) -> Result<(), Error> {
+		if self.dimensions == 0 {
+			return Err(Error::ZeroDimensions);
+		}
+		if self.ka.negative() {
+			return Err(Error::InvalidSettings("ka must not be negative"));
+		}
+		if self.kg.negative() {
+			return Err(Error::InvalidSettings("kg must not be negative"));
+		}
+		if self.kr.negative() {
+			return Err(Error::InvalidSettings("kr must not be negative"));
+		}
+		if self
+			.edge_types
+			.iter()
+			.any(|edge_type| edge_type.ka.negative())
+		{
+			return Err(Error::InvalidSettings("edge type ka must not be negative"));
+		}
+		if self.edge_weight_influence.negative() {
+			return Err(Error::InvalidSettings(
+				"edge_weight_influence must not be negative",
+			));
+		}
+		if !self.scaling_ratio.positive() {
+			return Err(Error::InvalidSettings("scaling_ratio must be positive"));
+		}
+		if !self.jitter_tolerance.positive() {
+			return Err(Error::InvalidSettings("jitter_tolerance must be positive"));
+		}
+		if !self.max_speed_ratio.positive() {
+			return Err(Error::InvalidSettings("max_speed_ratio must be positive"));
+		}
+		if let Some(krprime) = &self.prevent_overlapping {
+			if krprime.negative() {
+				return Err(Error::InvalidSettings(
+					"prevent_overlapping must not be negative",
+				));
+			}
+		}
+		if self.intra_group_attraction.negative()
+			|| self.inter_group_attraction.negative()
+			|| self.inter_group_repulsion.negative()
+		{
+			return Err(Error::InvalidSettings(
+				"group multipliers must not be negative",
+			));
+		}
+		if let Some(margin) = &self.pack_components {
+			if margin.negative() {
+				return Err(Error::InvalidSettings(
+					"pack_components must not be negative",
+				));
+			}
+		}
+		#[cfg(feature = "barnes_hut")]
+		if let Some(theta) = &self.barnes_hut {
+			if theta.negative() {
+				return Err(Error::InvalidSettings("barnes_hut must not be negative"));
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Stopping criteria of [`Layout::run_until_converged`]
+///
+/// The layout has converged when either criterion is met. A zero tolerance disables its criterion.
+/// The two are in different units: swinging stays of the order of the forces while the layout jitters around its equilibrium,
+/// so it is disabled by default.
+#[derive(Clone, Debug)]
+pub struct Tolerance<T> {
+	/// Maximum mean displacement of the nodes at an iteration, in layout units
+	pub displacement: T,
+	/// Maximum mean swinging of the nodes at an iteration (mass times the variation of the force, as used by the adaptive speed)
+	pub swinging: T,
+}
+
+impl<T: Coord> Default for Tolerance<T> {
+	fn default() -> Self {
+		Self {
+			displacement: T::from(0.001),
+			swinging: T::zero(),
+		}
+	}
+}
+
+/// Summary of [`Layout::run_until_converged`]
+pub struct ConvergenceReport<T> {
+	/// Number of computed iterations
+	pub iterations: usize,
+	/// Whether a tolerance was reached before the iteration cap
+	pub converged: bool,
+	/// Sum of the squared forces at the last iteration
+	pub energy: T,
+	/// Total displacement of the nodes at each iteration
+	pub displacements: Vec<T>,
+}
+
+/// Number of blocks of the parallel exact repulsion (fixed, so that results do not depend on the number of threads)
+#[cfg(feature = "parallel")]
+const REPULSION_BLOCKS: usize = 32;
+
+/// Bounds of at most `blocks` ranges of rows, having about the same number of pairs `(n1, n2)` with `n1 < n2`
+#[cfg(feature = "parallel")]
+fn pair_blocks(nb_nodes: usize, blocks: usize) -> Vec<usize> {
+	let blocks = blocks.min(nb_nodes).max(1);
+	let nb_pairs = nb_nodes * nb_nodes.saturating_sub(1) / 2;
+	let mut bounds = vec![0];
+	let mut pairs = 0;
+	for n1 in 0..nb_nodes {
+		pairs += nb_nodes - 1 - n1;
+		if pairs * blocks >= nb_pairs * bounds.len() && bounds.len() < blocks {
+			bounds.push(n1 + 1);
+		}
+	}
+	bounds.push(nb_nodes);
+	bounds.dedup();
+	bounds
+}
+
 pub struct Layout<T: Coord> {
 	pub edges: Vec<Edge>,
-	pub nodes: Vec<Node>,
+	/// Edge weights, in the same order as `edges` (`None` means all weights are `1`)
+	pub weights: Option<Vec<T>>,
+	/// Edge types, in the same order as `edges` (indices in `Settings::edge_types`, `None` for the global settings)
+	edge_types: Option<Vec<Option<usize>>>,
+	pub nodes: Vec<Node<T>>,
 	/// List of the nodes' positions
 	pub points: PointList<T>,
 	pub settings: Settings<T>,
 	speeds: PointList<T>,
 	old_speeds: PointList<T>, // speed at last iteration step (used for adaptive convergence)
+	/// Global speed, adjusted at each iteration
+	speed: T,
+	speed_efficiency: T,
+	/// Total displacement at last iteration
+	displacement: T,
+	/// Total swinging at last iteration
+	swinging: T,
+	/// Connected component of each node (empty when not packing components)
+	components: Vec<usize>,
+	/// Mean edge length, computed when a node is added and kept until the next iteration
+	edge_length: Option<T>,
 }
 
-impl<'a, T: Coord + std::fmt::Debug> Layout<T>
-where
-	Layout<T>: Repulsion,
-{
+impl<'a, T: Coord + std::fmt::Debug> Layout<T> {
 	/// Instanciates a randomly positioned layout from a directed graph
+	///
+	/// Fails if an edge refers to a node `>= nb_nodes`, or if the settings are invalid.
 	#[cfg(feature = "rand")]
-	pub fn from_graph(edges: Vec<Edge>, nb_nodes: usize, settings: Settings<T>) -> Self
+	pub fn from_graph(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
 	where
 		rand::distributions::Standard: rand::distributions::Distribution<T>,
 		T: rand::distributions::uniform::SampleUniform,
 	{
-		let mut nodes: Vec<Node> = (0..nb_nodes).map(|_| Node { degree: 0 }).collect();
-		for (n1, n2) in edges.iter() {
-			nodes.get_mut(*n1).unwrap().degree += 1;
-			nodes.get_mut(*n2).unwrap().degree += 1;
-		}
-		let nb = nb_nodes * settings.dimensions;
-		let null_coords = PointList {
-			dimensions: settings.dimensions,
-			points: (0..nb).map(|_| T::zero()).collect(),
-		};
-		Self {
-			nodes,
-			edges,
-			points: PointList {
-				dimensions: settings.dimensions,
-				points: {
-					let mut rng = rand::thread_rng();
-					(0..nb_nodes)
-						// .flat_map(|_| util::sample_unit_nsphere(&mut rng, settings.dimensions)) // unit sphere
-						.flat_map(|_| util::sample_unit_ncube(&mut rng, settings.dimensions)) // unit cube
-						.collect()
-				},
-			},
-			speeds: null_coords.clone(),
-			old_speeds: null_coords,
+		Self::from_graph_weighted(edges.into(), nb_nodes, settings)
+	}
+
+	/// Same as [`Layout::from_graph`], with edge weights (see [`EdgeList`])
+	#[cfg(feature = "rand")]
+	pub fn from_graph_weighted(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_with_rng_weighted(graph, nb_nodes, settings, &mut rand::thread_rng())
+	}
+
+	/// Instanciates a layout from a directed graph, positioned randomly using `rng`
+	///
+	/// The result only depends on `rng`'s state, so a seeded `rng` gives reproducible layouts.
+	#[cfg(feature = "rand")]
+	pub fn from_graph_with_rng<R: rand::Rng>(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_with_rng_weighted(edges.into(), nb_nodes, settings, rng)
+	}
+
+	/// Same as [`Layout::from_graph_with_rng`], with edge weights (see [`EdgeList`])
+	#[cfg(feature = "rand")]
+	pub fn from_graph_with_rng_weighted<R: rand::Rng>(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		settings.check()?;
+		let points = (0..nb_nodes)
+			.flat_map(|_| util::sample_unit_ncube(rng, settings.dimensions)) // unit cube
+			.collect();
+		Self::from_points(graph, points, settings)
+	}
+
+	/// Instanciates a layout from a directed graph, positioned randomly from `seed`
+	///
+	/// The same seed always gives the same layout (with the same version of `rand`, which provides the generator).
+	#[cfg(feature = "rand")]
+	pub fn from_graph_seeded(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_seeded_weighted(edges.into(), nb_nodes, settings, seed)
+	}
+
+	/// Same as [`Layout::from_graph_seeded`], with edge weights (see [`EdgeList`])
+	#[cfg(feature = "rand")]
+	pub fn from_graph_seeded_weighted(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		use rand::SeedableRng;
+		Self::from_graph_with_rng_weighted(
+			graph,
+			nb_nodes,
 			settings,
-		}
+			&mut rand::rngs::StdRng::seed_from_u64(seed),
+		)
+	}
+
+	/// Instanciates a layout from a directed graph, positioned by a quasi-random (Halton) sequence from `seed`
+	///
+	/// Available without the `rand` feature. The same seed always gives the same layout (see [`InitialPlacement::Halton`]).
+	pub fn from_graph_halton(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error> {
+		Self::from_graph_halton_weighted(edges.into(), nb_nodes, settings, seed)
+	}
+
+	/// Same as [`Layout::from_graph_halton`], with edge weights (see [`EdgeList`])
+	pub fn from_graph_halton_weighted(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error> {
+		Self::from_graph_with_placement_weighted(
+			graph,
+			nb_nodes,
+			settings,
+			InitialPlacement::Halton { seed },
+		)
+	}
+
+	/// Instanciates a layout from a directed graph, positioned by `placement`
+	pub fn from_graph_with_placement(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		placement: InitialPlacement<T>,
+	) -> Result<Self, Error> {
+		Self::from_graph_with_placement_weighted(edges.into(), nb_nodes, settings, placement)
+	}
+
+	/// Same as [`Layout::from_graph_with_placement`], with edge weights (see [`EdgeList`])
+	pub fn from_graph_with_placement_weighted(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		placement: InitialPlacement<T>,
+	) -> Result<Self, Error> {
+		settings.check()?;
+		let points = placement.place(nb_nodes, &graph.edges, settings.dimensions)?;
+		Self::from_points(graph, points, settings)
 	}
 
 	/// Instanciates layout from a directed graph, using initial positions
+	///
+	/// Fails if an edge refers to a missing node, if a position does not have `settings.dimensions` coordinates, or if the settings are invalid.
 	pub fn from_position_graph<I: Iterator<Item = &'a Position<T>>>(
 		edges: Vec<Edge>,
 		nodes: I,
 		settings: Settings<T>,
-	) -> Self
+	) -> Result<Self, Error>
+	where
+		T: 'a,
+	{
+		Self::from_position_graph_weighted(edges.into(), nodes, settings)
+	}
+
+	/// Same as [`Layout::from_position_graph`], with edge weights (see [`EdgeList`])
+	pub fn from_position_graph_weighted<I: Iterator<Item = &'a Position<T>>>(
+		graph: EdgeList<T>,
+		nodes: I,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
 	where
 		T: 'a,
 	{
+		settings.check()?;
 		let mut points = Vec::new();
-		let mut nodes: Vec<Node> = nodes
-			.map(|pos| {
-				points.extend_from_slice(pos);
-				Node { degree: 0 }
+		for (n, pos) in nodes.enumerate() {
+			if pos.len() != settings.dimensions {
+				return Err(Error::DimensionMismatch {
+					node: n,
+					expected: settings.dimensions,
+					found: pos.len(),
+				});
+			}
+			points.extend_from_slice(pos);
+		}
+		Self::from_points(graph, points, settings)
+	}
+
+	fn from_points(
+		graph: EdgeList<T>,
+		points: Vec<T>,
+		settings: Settings<T>,
+	) -> Result<Self, Error> {
+		let EdgeList {
+			edges,
+			weights,
+			types,
+		} = graph;
+		let nb_nodes = points.len() / settings.dimensions;
+		if nb_nodes == 0 {
+			return Err(Error::EmptyGraph);
+		}
+		for (e, (n1, n2)) in edges.iter().enumerate() {
+			for node in [n1, n2].iter() {
+				if **node >= nb_nodes {
+					return Err(Error::NodeIndex {
+						edge: Some(e),
+						node: **node,
+						nb_nodes,
+					});
+				}
+			}
+		}
+		if let Some(weights) = &weights {
+			if weights.len() != edges.len() {
+				return Err(Error::WeightCount {
+					expected: edges.len(),
+					found: weights.len(),
+				});
+			}
+			for (e, weight) in weights.iter().enumerate() {
+				check_weight(e, weight)?;
+			}
+		}
+		if let Some(types) = &types {
+			if types.len() != edges.len() {
+				return Err(Error::EdgeTypeCount {
+					expected: edges.len(),
+					found: types.len(),
+				});
+			}
+			for (e, edge_type) in types.iter().enumerate() {
+				check_edge_type(&settings, e, *edge_type)?;
+			}
+		}
+		let EdgeList {
+			edges,
+			weights,
+			types,
+		} = settings.normalization.normalize(EdgeList {
+			edges,
+			weights,
+			types,
+		});
+		let mut nodes: Vec<Node<T>> = (0..nb_nodes)
+			.map(|_| Node {
+				degree: 0,
+				mass: T::one(),
+				size: T::zero(),
+				fixed: false,
+				group: None,
 			})
 			.collect();
-
 		for (n1, n2) in edges.iter() {
-			nodes.get_mut(*n1).unwrap().degree += 1;
-			nodes.get_mut(*n2).unwrap().degree += 1;
+			nodes[*n1].degree += 1;
+			nodes[*n2].degree += 1;
 		}
-		let nb = nodes.len() * settings.dimensions;
 		let null_coords = PointList {
 			dimensions: settings.dimensions,
-			points: (0..nb).map(|_| T::zero()).collect(),
+			points: (0..points.len()).map(|_| T::zero()).collect(),
 		};
-		Self {
+		let mut layout = Self {
 			nodes,
 			edges,
+			weights,
+			edge_types: types,
 			points: PointList {
 				dimensions: settings.dimensions,
 				points,
 			},
 			speeds: null_coords.clone(),
 			old_speeds: null_coords,
+			speed: T::one(),
+			speed_efficiency: T::one(),
+			displacement: T::zero(),
+			swinging: T::zero(),
+			components: Vec::new(),
+			edge_length: None,
 			settings,
-		}
+		};
+		layout.update_masses();
+		Ok(layout)
 	}
 
-	/// Computes an iteration of ForceAtlas2
-	pub fn iteration(&mut self) {
-		self.init_iteration();
-		self.apply_attraction();
-		self.apply_repulsion();
-		self.apply_gravity();
-		self.apply_forces();
+	/// Computes the nodes' masses according to `settings.mass`
+	///
+	/// Call it after changing `settings.mass`, or the graph.
+	pub fn update_masses(&mut self) {
+		match &self.settings.mass {
+			Mass::Degree => {
+				for node in self.nodes.iter_mut() {
+					node.mass = T::from((node.degree + 1) as f32);
+				}
+			}
+			Mass::WeightedDegree => {
+				for node in self.nodes.iter_mut() {
+					node.mass = T::one();
+				}
+				for (e, (n1, n2)) in self.edges.iter().enumerate() {
+					let w = self.weights.as_ref().map_or_else(T::one, |w| w[e].clone());
+					self.nodes.get_mut(*n1).unwrap().mass += w.clone();
+					self.nodes.get_mut(*n2).unwrap().mass += w;
+				}
+			}
+			Mass::InDegree => {
+				for node in self.nodes.iter_mut() {
+					node.mass = T::one();
+				}
+				for (_n1, n2) in self.edges.iter() {
+					self.nodes.get_mut(*n2).unwrap().mass += T::one();
+				}
+			}
+			Mass::Custom(mass) => {
+				for (n, node) in self.nodes.iter_mut().enumerate() {
+					node.mass = mass(n, node);
+				}
+			}
+		}
 	}
 
-	fn init_iteration(&mut self) {
-		for (old_speed, speed) in izip!(
-			self.old_speeds.points.iter_mut(),
-			self.speeds.points.iter_mut(),
-		) {
-			*old_speed = speed.clone(); // keep memory of old speed
-			*speed = T::zero();
+	/// Adds a node linked to `neighbors`, returning its index
+	///
+	/// The node is placed at the centroid of its neighbours (or of the whole layout if it has none).
+	/// Its edges are `(node, neighbor)`, with weight `1`.
+	pub fn add_node(&mut self, neighbors: &[usize]) -> Result<usize, Error> {
+		let n = self.nodes.len();
+		for neighbor in neighbors.iter() {
+			self.check_node(*neighbor, Some(self.edges.len()))?;
+		}
+		self.push_node(neighbors);
+		for neighbor in neighbors.iter() {
+			self.link_edge((n, *neighbor), None);
 		}
+		self.update_custom_masses();
+		Ok(n)
 	}
 
-	fn apply_attraction(&mut self) {
-		if let Some((node_size, _)) = &self.settings.prevent_overlapping {
-			if self.settings.lin_log {
-				if self.settings.dissuade_hubs {
-					// PO LOG DH
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let n1_pos = self.points.get(*n1);
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						d = d.sqrt();
-
-						let dprime = d - node_size.clone();
-						if dprime.non_positive() {
-							continue;
-						}
-						let n1_degree = T::from(self.nodes.get(*n1).unwrap().degree);
-						let f =
-							dprime.clone().ln_1p() / dprime / n1_degree * self.settings.ka.clone();
-
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
-				} else {
-					// PO LOG --
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let n1_pos = self.points.get(*n1);
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						d = d.sqrt();
+	/// Adds a node without edges at the centroid of `neighbors`, with the mass of a node without edges
+	///
+	/// Call `update_custom_masses` afterwards.
+	fn push_node(&mut self, neighbors: &[usize]) -> usize {
+		let n = self.nodes.len();
+		let placed: Vec<usize> = if neighbors.is_empty() {
+			(0..n).collect()
+		} else {
+			neighbors.to_vec()
+		};
+		let mut pos: Vec<T> = (0..self.settings.dimensions).map(|_| T::zero()).collect();
+		for m in placed.iter() {
+			for (x, y) in pos.iter_mut().zip(self.points.get(*m).iter()) {
+				*x += y.clone();
+			}
+		}
+		if !placed.is_empty() {
+			let count = T::from(placed.len() as f32);
+			for x in pos.iter_mut() {
+				*x /= count.clone();
+			}
+		}
+		// Superposed nodes would never be separated: shift by a hundredth of the mean edge length
+		if neighbors.iter().any(|m| self.points.get(*m) == &pos[..]) {
+			pos[n % self.settings.dimensions] += self.edge_length() / T::from(100.0);
+		}
 
-						let dprime = d - node_size.clone();
-						if dprime.non_positive() {
-							continue;
-						}
-						let f = dprime.clone().ln_1p() / dprime * self.settings.ka.clone();
+		let null_coords: Vec<T> = (0..self.settings.dimensions).map(|_| T::zero()).collect();
+		self.points.push(&pos);
+		self.speeds.push(&null_coords);
+		self.old_speeds.push(&null_coords);
+		self.nodes.push(Node {
+			degree: 0,
+			mass: T::one(),
+			size: T::zero(),
+			fixed: false,
+			group: None,
+		});
+		n
+	}
 
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
-				}
-			} else {
-				if self.settings.dissuade_hubs {
-					// PO --- DH
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let n1_pos = self.points.get(*n1);
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						d = d.sqrt();
+	/// Mean edge length (`1` if it is zero or there is no edge), cached until the next iteration
+	fn edge_length(&mut self) -> T {
+		if self.edge_length.is_none() {
+			let points = &self.points;
+			self.edge_length = Some(
+				util::mean_edge_length(&self.edges, |n| Some(points.get(n))).unwrap_or_else(T::one),
+			);
+		}
+		self.edge_length.clone().unwrap()
+	}
 
-						let dprime = d.clone() - node_size.clone();
-						if dprime.non_positive() {
-							dbg!(dprime);
-							continue;
-						}
-						let n1_degree = T::from(self.nodes.get(*n1).unwrap().degree);
-						let f = dprime / d / n1_degree * self.settings.ka.clone();
+	/// Removes a node and its edges
+	///
+	/// The last node takes the index `n` (as in `Vec::swap_remove`).
+	/// A layout cannot be empty, so removing its only node fails with `Error::EmptyGraph`.
+	pub fn remove_node(&mut self, n: usize) -> Result<(), Error> {
+		self.check_node(n, None)?;
+		if self.nodes.len() == 1 {
+			return Err(Error::EmptyGraph);
+		}
+		// Drop the node's edges in a single pass
+		let kept: Vec<bool> = self
+			.edges
+			.iter()
+			.map(|(n1, n2)| *n1 != n && *n2 != n)
+			.collect();
+		for (e, keep) in kept.iter().enumerate() {
+			if !keep {
+				let edge = self.edges[e];
+				let weight = self.weights.as_ref().map_or_else(T::one, |w| w[e].clone());
+				self.nodes[edge.0].degree -= 1;
+				self.nodes[edge.1].degree -= 1;
+				self.shift_masses(edge, weight, false);
+			}
+		}
+		fn retain<E>(v: &mut Vec<E>, kept: &[bool]) {
+			let mut kept = kept.iter();
+			v.retain(|_| *kept.next().unwrap());
+		}
+		retain(&mut self.edges, &kept);
+		if let Some(weights) = &mut self.weights {
+			retain(weights, &kept);
+		}
+		if let Some(edge_types) = &mut self.edge_types {
+			retain(edge_types, &kept);
+		}
+		let last = self.nodes.len() - 1;
+		for (n1, n2) in self.edges.iter_mut() {
+			if *n1 == last {
+				*n1 = n;
+			}
+			if *n2 == last {
+				*n2 = n;
+			}
+		}
+		self.nodes.swap_remove(n);
+		self.points.swap_remove(n);
+		self.speeds.swap_remove(n);
+		self.old_speeds.swap_remove(n);
+		self.update_custom_masses();
+		Ok(())
+	}
 
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
-				} else {
-					// PO --- --
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let n1_pos = self.points.get(*n1).clone();
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						d = d.sqrt();
+	/// Adds an edge, returning its index
+	///
+	/// `weight` defaults to `1`, and must be finite and non-negative.
+	pub fn add_edge(&mut self, edge: Edge, weight: Option<T>) -> Result<usize, Error> {
+		let e = self.edges.len();
+		self.check_node(edge.0, Some(e))?;
+		self.check_node(edge.1, Some(e))?;
+		if let Some(weight) = &weight {
+			check_weight(e, weight)?;
+		}
+		self.link_edge(edge, weight);
+		self.update_custom_masses();
+		Ok(e)
+	}
 
-						let dprime = d.clone() - node_size.clone();
-						if dprime.non_positive() {
-							continue;
-						}
-						let f = dprime / d * self.settings.ka.clone();
+	/// Removes an edge, returning it
+	///
+	/// The following edges are shifted (as in `Vec::remove`).
+	pub fn remove_edge(&mut self, e: usize) -> Result<Edge, Error> {
+		if e >= self.edges.len() {
+			return Err(Error::EdgeIndex {
+				edge: e,
+				nb_edges: self.edges.len(),
+			});
+		}
+		let edge = self.unlink_edge(e);
+		self.update_custom_masses();
+		Ok(edge)
+	}
 
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
-				}
+	fn check_node(&self, n: usize, edge: Option<usize>) -> Result<(), Error> {
+		if n < self.nodes.len() {
+			Ok(())
+		} else {
+			Err(Error::NodeIndex {
+				edge,
+				node: n,
+				nb_nodes: self.nodes.len(),
+			})
+		}
+	}
+
+	/// Adds an edge, updating the masses of its ends
+	///
+	/// Call `update_custom_masses` afterwards.
+	fn link_edge(&mut self, (n1, n2): Edge, weight: Option<T>) {
+		match (&mut self.weights, weight.clone()) {
+			(Some(weights), weight) => weights.push(weight.unwrap_or_else(T::one)),
+			(None, Some(weight)) => {
+				let mut weights: Vec<T> = self.edges.iter().map(|_| T::one()).collect();
+				weights.push(weight);
+				self.weights = Some(weights);
 			}
+			(None, None) => {}
+		}
+		if let Some(edge_types) = &mut self.edge_types {
+			edge_types.push(None);
+		}
+		self.edges.push((n1, n2));
+		self.nodes[n1].degree += 1;
+		self.nodes[n2].degree += 1;
+		self.shift_masses((n1, n2), weight.unwrap_or_else(T::one), true);
+	}
+
+	/// Removes an edge, updating the masses of its ends
+	///
+	/// Call `update_custom_masses` afterwards.
+	fn unlink_edge(&mut self, e: usize) -> Edge {
+		let (n1, n2) = self.edges.remove(e);
+		let weight = match &mut self.weights {
+			Some(weights) => weights.remove(e),
+			None => T::one(),
+		};
+		if let Some(edge_types) = &mut self.edge_types {
+			edge_types.remove(e);
+		}
+		self.nodes[n1].degree -= 1;
+		self.nodes[n2].degree -= 1;
+		self.shift_masses((n1, n2), weight, false);
+		(n1, n2)
+	}
+
+	/// Adds (or removes, if not `added`) the contribution of an edge to the masses of its ends
+	///
+	/// `Mass::Custom` masses are left unchanged, as they may depend on the whole graph.
+	fn shift_masses(&mut self, (n1, n2): Edge, weight: T, added: bool) {
+		let (m1, m2) = match &self.settings.mass {
+			Mass::Degree => (T::one(), T::one()),
+			Mass::WeightedDegree => (weight.clone(), weight),
+			Mass::InDegree => (T::zero(), T::one()),
+			Mass::Custom(_) => return,
+		};
+		if added {
+			self.nodes[n1].mass += m1;
+			self.nodes[n2].mass += m2;
 		} else {
-			if self.settings.lin_log {
-				if self.settings.dissuade_hubs {
-					// -- LOG DH
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= self.points.get(*n1)[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						if d.is_zero() {
-							continue;
-						}
-						d = d.sqrt();
+			self.nodes[n1].mass -= m1;
+			self.nodes[n2].mass -= m2;
+		}
+	}
 
-						let n1_degree = T::from(self.nodes.get(*n1).unwrap().degree);
-						let f = d.clone().ln_1p() / d / n1_degree * self.settings.ka.clone();
+	/// Recomputes the masses if they are `Mass::Custom`, after a change of the graph
+	fn update_custom_masses(&mut self) {
+		if let Mass::Custom(_) = self.settings.mass {
+			self.update_masses();
+		}
+	}
 
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
-				} else {
-					// -- LOG --
-					for (n1, n2) in self.edges.iter() {
-						let mut d = T::zero();
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= self.points.get(*n1)[i].clone();
-							d += di[i].clone().pow_n(2u32);
-						}
-						if d.is_zero() {
-							continue;
-						}
-						d = d.sqrt();
+	/// Types of the edges, in the same order as `edges` (`None` if no edge is typed)
+	pub fn edge_types(&self) -> Option<&[Option<usize>]> {
+		self.edge_types.as_deref()
+	}
 
-						let f = d.clone().ln_1p() / d * self.settings.ka.clone();
+	/// Sets the type of every edge, in the same order as `edges`
+	///
+	/// The type of an edge is its index in `Settings::edge_types`, whose `ka` and `lin_log` are then used instead of the global ones.
+	/// Edges without type (`None`), including the ones added later, use the global settings.
+	pub fn set_edge_types(&mut self, edge_types: Vec<Option<usize>>) -> Result<(), Error> {
+		if edge_types.len() != self.edges.len() {
+			return Err(Error::EdgeTypeCount {
+				expected: self.edges.len(),
+				found: edge_types.len(),
+			});
+		}
+		for (e, edge_type) in edge_types.iter().enumerate() {
+			check_edge_type(&self.settings, e, *edge_type)?;
+		}
+		self.edge_types = Some(edge_types);
+		Ok(())
+	}
 
-						let n1_speed = self.speeds.get_mut(*n1);
-						for i in 0usize..self.settings.dimensions {
-							n1_speed[i] += f.clone() * di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= f.clone() * di[i].clone();
-						}
-					}
+	/// Sets the type of an edge (`None` for the global settings), leaving the other edges unchanged
+	pub fn set_edge_type(&mut self, e: usize, edge_type: Option<usize>) -> Result<(), Error> {
+		if e >= self.edges.len() {
+			return Err(Error::EdgeIndex {
+				edge: e,
+				nb_edges: self.edges.len(),
+			});
+		}
+		check_edge_type(&self.settings, e, edge_type)?;
+		let nb_edges = self.edges.len();
+		self.edge_types.get_or_insert_with(|| vec![None; nb_edges])[e] = edge_type;
+		Ok(())
+	}
+
+	/// Attraction coefficient and lin-log mode of an edge
+	///
+	/// Edges whose type is missing from `Settings::edge_types` use the global settings.
+	#[inline]
+	fn edge_attraction(&self, e: usize) -> (&T, bool) {
+		match self
+			.edge_types
+			.as_ref()
+			.and_then(|edge_types| edge_types[e])
+			.and_then(|edge_type| self.settings.edge_types.get(edge_type))
+		{
+			Some(edge_type) => (&edge_type.ka, edge_type.lin_log),
+			None => (&self.settings.ka, self.settings.lin_log),
+		}
+	}
+
+	/// Weight of an edge, with `edge_weight_influence` applied
+	#[inline]
+	fn edge_weight(&self, e: usize) -> T {
+		match &self.weights {
+			None => T::one(),
+			Some(weights) => {
+				let influence = &self.settings.edge_weight_influence;
+				if influence.is_zero() {
+					T::one()
+				} else if *influence == T::one() {
+					weights[e].clone()
+				} else {
+					(weights[e].clone().ln() * influence.clone()).exp()
 				}
+			}
+		}
+	}
+
+	/// Computes an iteration of ForceAtlas2
+	pub fn iteration(&mut self) {
+		self.init_iteration();
+		self.apply_attraction();
+		self.apply_repulsion();
+		self.apply_gravity();
+		self.apply_forces();
+		if let Some(margin) = self.settings.pack_components.clone() {
+			self.pack_components(margin);
+		}
+	}
+
+	/// Computes iterations until the nodes stop moving, or until `max_iterations` is reached
+	///
+	/// Stops when the mean displacement of the nodes falls below `tolerance.displacement`,
+	/// or when their mean swinging falls below `tolerance.swinging`.
+	pub fn run_until_converged(
+		&mut self,
+		tolerance: &Tolerance<T>,
+		max_iterations: usize,
+	) -> ConvergenceReport<T> {
+		let nb_nodes = T::from(self.nodes.len() as f32);
+		let mut displacements = Vec::new();
+		let mut converged = false;
+		while displacements.len() < max_iterations {
+			self.iteration();
+			displacements.push(self.displacement.clone());
+			if self.displacement.clone() / nb_nodes.clone() < tolerance.displacement
+				|| self.swinging.clone() / nb_nodes.clone() < tolerance.swinging
+			{
+				converged = true;
+				break;
+			}
+		}
+		ConvergenceReport {
+			iterations: displacements.len(),
+			converged,
+			energy: self
+				.speeds
+				.points
+				.iter()
+				.fold(T::zero(), |energy, f| energy + f.clone().powi(2)),
+			displacements,
+		}
+	}
+
+	fn init_iteration(&mut self) {
+		for (old_speed, speed) in izip!(
+			self.old_speeds.points.iter_mut(),
+			self.speeds.points.iter_mut(),
+		) {
+			*old_speed = speed.clone(); // keep memory of old speed
+			*speed = T::zero();
+		}
+		self.edge_length = None;
+		if self.settings.pack_components.is_some() {
+			self.components = util::components(self.nodes.len(), &self.edges);
+		} else {
+			self.components.clear();
+		}
+	}
+
+	/// Attraction factor of an edge, `di` being the vector from `n1` to `n2`
+	///
+	/// The force applied to `n1` is `f * di`, and the opposite is applied to `n2`.
+	#[inline]
+	fn attraction_factor(&self, e: usize, n1: usize, n2: usize, di: &[T]) -> Option<T> {
+		let (ka, lin_log) = self.edge_attraction(e);
+		let mut f = ka.clone() * self.edge_weight(e);
+		if self.settings.dissuade_hubs {
+			f /= T::from(self.nodes.get(n1).unwrap().degree as f32);
+		}
+		match (self.nodes[n1].group, self.nodes[n2].group) {
+			(Some(g1), Some(g2)) if g1 == g2 => f *= self.settings.intra_group_attraction.clone(),
+			(Some(_), Some(_)) => f *= self.settings.inter_group_attraction.clone(),
+			_ => {}
+		}
+		if self.settings.prevent_overlapping.is_some() {
+			let d = util::norm(di);
+			let dprime = d.clone()
+				- self.nodes.get(n1).unwrap().size.clone()
+				- self.nodes.get(n2).unwrap().size.clone();
+			if dprime.non_positive() {
+				return None;
+			}
+			if lin_log {
+				f *= dprime.clone().ln_1p() / dprime;
 			} else {
-				if self.settings.dissuade_hubs {
-					// -- --- DH
-					for (n1, n2) in self.edges.iter() {
-						let n1_speed = self.speeds.get_mut(*n1);
-						let n1_pos = self.points.get(*n1).clone();
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						let n1_degree = T::from(self.nodes.get(*n1).unwrap().degree);
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							di[i] /= n1_degree.clone();
-							di[i] *= self.settings.ka.clone();
-							n1_speed[i] += di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= di[i].clone();
-						}
+				f *= dprime / d;
+			}
+		} else if lin_log {
+			let d = util::norm(di);
+			if d.is_zero() {
+				return None;
+			}
+			f *= d.clone().ln_1p() / d;
+		}
+		Some(f)
+	}
+
+	/// Attraction force applied to the source of an edge (the opposite is applied to the target)
+	fn attraction_force(&self, e: usize) -> Option<Vec<T>> {
+		let (n1, n2) = self.edges[e];
+		let n1_pos = self.points.get(n1);
+		let mut di = self.points.get_clone(n2);
+		for i in 0usize..self.settings.dimensions {
+			di[i] -= n1_pos[i].clone();
+		}
+		let f = self.attraction_factor(e, n1, n2, &di)?;
+		for x in di.iter_mut() {
+			*x *= f.clone();
+		}
+		Some(di)
+	}
+
+	fn apply_attraction(&mut self) {
+		// Forces are computed in parallel, then summed in edge order to stay deterministic
+		#[cfg(feature = "parallel")]
+		{
+			let forces: Vec<Option<Vec<T>>> = (0..self.edges.len())
+				.into_par_iter()
+				.map(|e| self.attraction_force(e))
+				.collect();
+			for ((n1, n2), force) in self.edges.iter().zip(forces) {
+				if let Some(force) = force {
+					let n1_speed = self.speeds.get_mut(*n1);
+					for i in 0usize..self.settings.dimensions {
+						n1_speed[i] += force[i].clone();
 					}
-				} else {
-					// -- --- -- DEFAULT attraction
-					for (n1, n2) in self.edges.iter() {
-						let n1_speed = self.speeds.get_mut(*n1);
-						let n1_pos = self.points.get(*n1).clone();
-						let mut di_v = self.points.get_clone(*n2);
-						let di = di_v.as_mut_slice();
-						for i in 0usize..self.settings.dimensions {
-							di[i] -= n1_pos[i].clone();
-							di[i] *= self.settings.ka.clone();
-							n1_speed[i] += di[i].clone();
-						}
-						let n2_speed = self.speeds.get_mut(*n2);
-						for i in 0usize..self.settings.dimensions {
-							n2_speed[i] -= di[i].clone();
-						}
+					let n2_speed = self.speeds.get_mut(*n2);
+					for i in 0usize..self.settings.dimensions {
+						n2_speed[i] -= force[i].clone();
 					}
 				}
 			}
 		}
+		#[cfg(not(feature = "parallel"))]
+		for e in 0..self.edges.len() {
+			if let Some(force) = self.attraction_force(e) {
+				let (n1, n2) = self.edges[e];
+				let n1_speed = self.speeds.get_mut(n1);
+				for i in 0usize..self.settings.dimensions {
+					n1_speed[i] += force[i].clone();
+				}
+				let n2_speed = self.speeds.get_mut(n2);
+				for i in 0usize..self.settings.dimensions {
+					n2_speed[i] -= force[i].clone();
+				}
+			}
+		}
 	}
 
 	fn apply_gravity(&mut self) {
-		if self.settings.strong_gravity {
-			for (n, pos, speed) in izip!(
-				self.nodes.iter(),
-				self.points.iter(),
-				self.speeds.iter_mut()
-			) {
-				let f = T::from(n.degree + 1) * self.settings.kg.clone();
-				for i in 0usize..self.settings.dimensions {
-					speed[i] -= f.clone() * pos[i].clone();
+		let settings = &self.settings;
+		// Each component is attracted to its own center
+		let components = &self.components;
+		let centers = self.component_centers();
+		let gravity = |n: usize, node: &Node<T>, pos: &[T], speed: &mut [T]| {
+			let mut pos = util::clone_slice_mut(pos);
+			if let Some(component) = components.get(n) {
+				for (x, center) in pos.iter_mut().zip(centers[*component].iter()) {
+					*x -= center.clone();
 				}
 			}
-		} else {
-			// DEFAULT gravity
-			for (n, pos, speed) in izip!(
-				self.nodes.iter(),
-				self.points.iter(),
-				self.speeds.iter_mut()
-			) {
-				let d = util::norm(pos);
+			let f = if settings.strong_gravity {
+				node.mass.clone() * settings.kg.clone()
+			} else {
+				// DEFAULT gravity
+				let d = util::norm(&pos);
 				if d.is_zero() {
-					continue;
-				}
-				let f = T::from(n.degree + 1) * self.settings.kg.clone() / d;
-				for i in 0usize..self.settings.dimensions {
-					speed[i] -= f.clone() * pos[i].clone();
+					return;
 				}
+				node.mass.clone() * settings.kg.clone() / d
+			};
+			for i in 0usize..settings.dimensions {
+				speed[i] -= f.clone() * pos[i].clone();
+			}
+		};
+		#[cfg(feature = "parallel")]
+		self.nodes
+			.par_iter()
+			.zip(self.points.points.par_chunks(settings.dimensions))
+			.zip(self.speeds.points.par_chunks_mut(settings.dimensions))
+			.enumerate()
+			.for_each(|(n, ((node, pos), speed))| gravity(n, node, pos, speed));
+		#[cfg(not(feature = "parallel"))]
+		self.nodes
+			.iter()
+			.zip(self.points.iter())
+			.zip(self.speeds.iter_mut())
+			.enumerate()
+			.for_each(|(n, ((node, pos), speed))| gravity(n, node, pos, speed));
+	}
+
+	/// Mean position of each component
+	fn component_centers(&self) -> Vec<Vec<T>> {
+		let nb_components = self.components.iter().max().map_or(0, |c| c + 1);
+		let mut centers: Vec<Vec<T>> = (0..nb_components)
+			.map(|_| (0..self.settings.dimensions).map(|_| T::zero()).collect())
+			.collect();
+		let mut counts = vec![0usize; nb_components];
+		for (component, pos) in self.components.iter().zip(self.points.iter()) {
+			counts[*component] += 1;
+			for (x, y) in centers[*component].iter_mut().zip(pos.iter()) {
+				*x += y.clone();
+			}
+		}
+		for (center, count) in centers.iter_mut().zip(counts) {
+			let count = T::from(count as f32);
+			for x in center.iter_mut() {
+				*x /= count.clone();
 			}
 		}
+		centers
 	}
 
-	#[inline]
-	fn inner_apply_repulsion(&mut self) {
-		if let Some((node_size, krprime)) = &self.settings.prevent_overlapping {
-			for n1 in 0..self.nodes.len() - 1 {
-				let n1_pos = self.points.get(n1);
-				for n2 in n1 + 1..self.nodes.len() {
-					let mut d = T::zero();
-					let mut di_v = self.points.get_clone(n2);
-					let di = di_v.as_mut_slice();
-					for i in 0usize..self.settings.dimensions {
-						di[i] -= n1_pos[i].clone();
-						d += di[i].clone().pow_n(2u32);
+	/// Translates the components so that their bounding boxes are packed in rows
+	///
+	/// Components containing a fixed node stay in place, and the packed rows are put next to their bounding box.
+	fn pack_components(&mut self, margin: T) {
+		let dimensions = self.settings.dimensions;
+		let nb_components = self.components.iter().max().map_or(0, |c| c + 1);
+		let mut mins: Vec<Option<Vec<T>>> = (0..nb_components).map(|_| None).collect();
+		let mut maxs: Vec<Vec<T>> = (0..nb_components).map(|_| Vec::new()).collect();
+		let mut movable = vec![true; nb_components];
+		for (n, (component, pos)) in self.components.iter().zip(self.points.iter()).enumerate() {
+			let node = &self.nodes[n];
+			if node.fixed {
+				movable[*component] = false;
+			}
+			match &mut mins[*component] {
+				Some(min) => {
+					let max = &mut maxs[*component];
+					for i in 0..dimensions {
+						min[i] = util::min(min[i].clone(), pos[i].clone() - node.size.clone());
+						max[i] = util::max(max[i].clone(), pos[i].clone() + node.size.clone());
 					}
-					d = d.sqrt();
+				}
+				min => {
+					*min = Some(pos.iter().map(|x| x.clone() - node.size.clone()).collect());
+					maxs[*component] = pos.iter().map(|x| x.clone() + node.size.clone()).collect();
+				}
+			}
+		}
+		let mins: Vec<Vec<T>> = mins.into_iter().map(Option::unwrap).collect();
+		let packed: Vec<usize> = (0..nb_components).filter(|c| movable[*c]).collect();
+		if packed.is_empty() {
+			return;
+		}
 
-					let dprime = d.clone() - node_size.clone();
-					let f = if dprime.positive() {
-						self.settings.kr.clone() / dprime
-					} else if dprime.is_zero() {
-						continue;
-					} else {
-						krprime.clone()
-					} * T::from(
-						(unsafe { self.nodes.get_unchecked(n1) }.degree + 1)
-							* (unsafe { self.nodes.get_unchecked(n2) }.degree + 1),
-					) / d;
+		// Shelf packing: tallest components first, in rows of roughly the same width as the total height
+		let extent = |c: usize, i: usize| {
+			if i < dimensions {
+				maxs[c][i].clone() - mins[c][i].clone() + margin.clone()
+			} else {
+				T::zero()
+			}
+		};
+		let mut order = packed.clone();
+		order.sort_by(|c1, c2| {
+			extent(*c2, 1)
+				.partial_cmp(&extent(*c1, 1))
+				.unwrap_or(std::cmp::Ordering::Equal)
+		});
+		let mut row_width = T::zero();
+		let mut area = T::zero();
+		for c in packed.iter() {
+			row_width = util::max(row_width, extent(*c, 0));
+			area += extent(*c, 0) * extent(*c, 1);
+		}
+		row_width = util::max(row_width, area.sqrt());
+		let mut corners: Vec<(T, T)> = (0..nb_components).map(|_| (T::zero(), T::zero())).collect();
+		let (mut x, mut y, mut row_height) = (T::zero(), T::zero(), T::zero());
+		let mut width = T::zero();
+		for c in order.into_iter() {
+			// In 1D, all the components are on the same row
+			if dimensions > 1 && x.positive() && x.clone() + extent(c, 0) > row_width {
+				x = T::zero();
+				y += row_height;
+				row_height = T::zero();
+			}
+			corners[c] = (x.clone(), y.clone());
+			x += extent(c, 0);
+			width = util::max(width, x.clone());
+			row_height = util::max(row_height, extent(c, 1));
+		}
+		let height = y + row_height;
+		// Size of the arrangement, without the trailing margins
+		let (width, height) = (width - margin.clone(), height - margin.clone());
 
-					let n1_speed = self.speeds.get_mut(n1);
-					for i in 0usize..self.settings.dimensions {
-						n1_speed[i] -= f.clone() * di[i].clone();
-					}
-					let n2_speed = self.speeds.get_mut(n2);
-					for i in 0usize..self.settings.dimensions {
-						n2_speed[i] += f.clone() * di[i].clone();
+		// Bounding box of the fixed components, which the arrangement must not overlap
+		let mut fixed: Option<(Vec<T>, Vec<T>)> = None;
+		for c in (0..nb_components).filter(|c| !movable[*c]) {
+			match &mut fixed {
+				Some((min, max)) => {
+					for i in 0..dimensions {
+						min[i] = util::min(min[i].clone(), mins[c][i].clone());
+						max[i] = util::max(max[i].clone(), maxs[c][i].clone());
 					}
 				}
+				None => fixed = Some((mins[c].clone(), maxs[c].clone())),
+			}
+		}
+		// Corner of the arrangement and center on the other dimensions:
+		// centered on the origin, or next to the fixed components (on the side keeping the whole layout the most square)
+		let two = T::from(2.0);
+		let (origin, center): ((T, T), Vec<T>) = match &fixed {
+			None => (
+				(-width.clone() / two.clone(), -height.clone() / two.clone()),
+				(0..dimensions).map(|_| T::zero()).collect(),
+			),
+			Some((min, max)) => {
+				let center: Vec<T> = min
+					.iter()
+					.zip(max.iter())
+					.map(|(a, b)| (a.clone() + b.clone()) / two.clone())
+					.collect();
+				let fixed_width = max[0].clone() - min[0].clone();
+				let right = if dimensions > 1 {
+					let fixed_height = max[1].clone() - min[1].clone();
+					util::max(
+						fixed_width.clone() + margin.clone() + width.clone(),
+						util::max(fixed_height.clone(), height.clone()),
+					) <= util::max(
+						util::max(fixed_width, width.clone()),
+						fixed_height + margin.clone() + height.clone(),
+					)
+				} else {
+					true
+				};
+				if right {
+					let y = if dimensions > 1 {
+						center[1].clone() - height.clone() / two.clone()
+					} else {
+						T::zero()
+					};
+					((max[0].clone() + margin.clone(), y), center)
+				} else {
+					(
+						(
+							center[0].clone() - width.clone() / two.clone(),
+							max[1].clone() + margin.clone(),
+						),
+						center,
+					)
+				}
+			}
+		};
+		let offsets: Vec<Vec<T>> = (0..nb_components)
+			.map(|c| {
+				(0..dimensions)
+					.map(|i| {
+						if !movable[c] {
+							T::zero()
+						} else if i == 0 {
+							origin.0.clone() + corners[c].0.clone() - mins[c][0].clone()
+						} else if i == 1 {
+							origin.1.clone() + corners[c].1.clone() - mins[c][1].clone()
+						} else {
+							center[i].clone()
+								- (mins[c][i].clone() + maxs[c][i].clone()) / two.clone()
+						}
+					})
+					.collect()
+			})
+			.collect();
+		for (component, pos) in self.components.iter().zip(self.points.iter_mut()) {
+			for (x, offset) in pos.iter_mut().zip(offsets[*component].iter()) {
+				*x += offset.clone();
 			}
+		}
+	}
+
+	fn apply_repulsion(&mut self) {
+		if self.nodes.is_empty() {
+			return;
+		}
+		#[cfg(feature = "barnes_hut")]
+		if let Some(theta) = self.settings.barnes_hut.clone() {
+			return self.inner_apply_repulsion_barnes_hut(theta);
+		}
+		self.inner_apply_repulsion()
+	}
+
+	/// Repulsion factor between two nodes, `d2` being their squared distance
+	///
+	/// The force applied to `n1` is `-f * di`, `di` being the vector from `n1` to `n2`.
+	#[inline]
+	fn repulsion_factor(settings: &Settings<T>, n1: &Node<T>, n2: &Node<T>, d2: T) -> Option<T> {
+		let f = Self::base_repulsion_factor(settings, n1, n2, d2)?;
+		match (n1.group, n2.group) {
+			(Some(g1), Some(g2)) if g1 != g2 => Some(f * settings.inter_group_repulsion.clone()),
+			_ => Some(f),
+		}
+	}
+
+	/// Repulsion factor, without group multiplier
+	#[inline]
+	fn base_repulsion_factor(
+		settings: &Settings<T>,
+		n1: &Node<T>,
+		n2: &Node<T>,
+		d2: T,
+	) -> Option<T> {
+		if let Some(krprime) = &settings.prevent_overlapping {
+			let d = d2.sqrt();
+			let dprime = d.clone() - n1.size.clone() - n2.size.clone();
+			let f = if dprime.positive() {
+				settings.kr.clone() / dprime
+			} else if dprime.is_zero() {
+				return None;
+			} else {
+				krprime.clone()
+			};
+			Some(f * n1.mass.clone() * n2.mass.clone() / d)
 		} else {
 			// DEFAULT repulsion
-			for n1 in 0..self.nodes.len() - 1 {
-				let n1_pos = self.points.get(n1);
-				for n2 in n1 + 1..self.nodes.len() {
-					let mut d2 = T::zero();
-					let mut di_v = self.points.get_clone(n2);
-					let di = di_v.as_mut_slice();
-					for i in 0usize..self.settings.dimensions {
-						di[i] -= n1_pos[i].clone();
-						d2 += di[i].clone().pow_n(2u32);
+			if d2.is_zero() {
+				return None; // n1 and n2 are superposed (usually n1 is n2)
+			}
+			Some(n1.mass.clone() * n2.mass.clone() / d2 * settings.kr.clone())
+		}
+	}
+
+	#[inline]
+	fn inner_apply_repulsion(&mut self) {
+		// Rows of the pair triangle are split into blocks of about the same number of pairs,
+		// each block summing its forces in its own buffer. The buffers are added in block order,
+		// so that the result does not depend on the number of threads.
+		#[cfg(feature = "parallel")]
+		{
+			let nb_nodes = self.nodes.len();
+			let dimensions = self.settings.dimensions;
+			let bounds = pair_blocks(nb_nodes, REPULSION_BLOCKS);
+			let (settings, nodes, points, components) =
+				(&self.settings, &self.nodes, &self.points, &self.components);
+			let forces: Vec<Vec<T>> = bounds
+				.par_windows(2)
+				.map(|rows| {
+					let mut forces: Vec<T> =
+						(0..nb_nodes * dimensions).map(|_| T::zero()).collect();
+					Self::repulse_rows(
+						settings,
+						nodes,
+						points,
+						components,
+						rows[0]..rows[1],
+						&mut forces,
+					);
+					forces
+				})
+				.collect();
+			self.speeds
+				.points
+				.par_chunks_mut(dimensions)
+				.enumerate()
+				.for_each(|(n, speed)| {
+					for block in forces.iter() {
+						for (v, f) in speed.iter_mut().zip(&block[n * dimensions..]) {
+							*v += f.clone();
+						}
 					}
-					if d2.is_zero() {
-						continue; // n1 and n2 are superposed (usually n1 is n2)
+				});
+		}
+		#[cfg(not(feature = "parallel"))]
+		Self::repulse_rows(
+			&self.settings,
+			&self.nodes,
+			&self.points,
+			&self.components,
+			0..self.nodes.len(),
+			&mut self.speeds.points,
+		);
+	}
+
+	/// Adds to `forces` the repulsion of the pairs `(n1, n2)` with `n1` in `rows` and `n1 < n2`
+	///
+	/// `forces` has the layout of `PointList::points`.
+	#[inline]
+	fn repulse_rows(
+		settings: &Settings<T>,
+		nodes: &[Node<T>],
+		points: &PointList<T>,
+		components: &[usize],
+		rows: std::ops::Range<usize>,
+		forces: &mut [T],
+	) {
+		let dimensions = settings.dimensions;
+		for n1 in rows {
+			let n1_pos = points.get(n1);
+			for n2 in n1 + 1..nodes.len() {
+				if components.get(n1) != components.get(n2) {
+					continue;
+				}
+				let mut d2 = T::zero();
+				let mut di_v = points.get_clone(n2);
+				let di = di_v.as_mut_slice();
+				for i in 0usize..dimensions {
+					di[i] -= n1_pos[i].clone();
+					d2 += di[i].clone().powi(2);
+				}
+				let f = match Self::repulsion_factor(
+					settings,
+					unsafe { nodes.get_unchecked(n1) },
+					unsafe { nodes.get_unchecked(n2) },
+					d2,
+				) {
+					Some(f) => f,
+					None => continue,
+				};
+
+				for i in 0usize..dimensions {
+					forces[n1 * dimensions + i] -= f.clone() * di[i].clone();
+				}
+				for i in 0usize..dimensions {
+					forces[n2 * dimensions + i] += f.clone() * di[i].clone();
+				}
+			}
+		}
+	}
+
+	#[cfg(feature = "barnes_hut")]
+	fn inner_apply_repulsion_barnes_hut(&mut self, theta: T) {
+		// One tree per component, so that components do not interact
+		let mut members: Vec<Vec<usize>> = Vec::new();
+		for (n, component) in self.components.iter().enumerate() {
+			if *component >= members.len() {
+				members.resize_with(component + 1, Vec::new);
+			}
+			members[*component].push(n);
+		}
+		if self.components.is_empty() {
+			members.push((0..self.nodes.len()).collect());
+		}
+		let trees: Vec<barnes_hut::Tree<T>> = members
+			.iter()
+			.map(|bodies| barnes_hut::Tree::new(&self.points, &self.nodes, bodies))
+			.collect();
+		let points = &self.points;
+		let nodes = &self.nodes;
+		let settings = &self.settings;
+		let components = &self.components;
+		let repulse = |(n1, n1_speed): (usize, &mut [T])| {
+			let node1 = &nodes[n1];
+			let tree = &trees[components.get(n1).copied().unwrap_or(0)];
+			tree.for_each_interaction(points, n1, &theta, |body, di, d2| {
+				let f = match body {
+					barnes_hut::Body::Node(n2) => {
+						Self::repulsion_factor(settings, node1, &nodes[n2], d2)
 					}
+					// Sizes are ignored for clusters, as in Gephi
+					barnes_hut::Body::Cluster(mass) => {
+						if d2.is_zero() {
+							None
+						} else {
+							let mass = mass.seen_by(node1.group, &settings.inter_group_repulsion);
+							Some(node1.mass.clone() * mass / d2 * settings.kr.clone())
+						}
+					}
+				};
+				if let Some(f) = f {
+					for i in 0usize..settings.dimensions {
+						n1_speed[i] -= f.clone() * di[i].clone();
+					}
+				}
+			});
+		};
+		#[cfg(feature = "parallel")]
+		self.speeds
+			.points
+			.par_chunks_mut(settings.dimensions)
+			.enumerate()
+			.for_each(repulse);
+		#[cfg(not(feature = "parallel"))]
+		self.speeds.iter_mut().enumerate().for_each(repulse);
+	}
+
+	fn apply_forces(&mut self) {
+		let dimensions = self.settings.dimensions;
+		// Swinging measures oscillations, traction measures continuity
+		let swinging_traction = |((node, speed), old_speed): ((&Node<T>, &[T]), &[T])| -> (T, T) {
+			if node.fixed {
+				return (T::zero(), T::zero());
+			}
+			let mut swinging2 = T::zero();
+			let mut traction2 = T::zero();
+			for i in 0usize..dimensions {
+				// Note: consider expecting T which is Copy
+				swinging2 += (old_speed[i].clone() - speed[i].clone()).powi(2);
+				traction2 += (old_speed[i].clone() + speed[i].clone()).powi(2);
+			}
+			(
+				node.mass.clone() * swinging2.sqrt(),
+				node.mass.clone() * traction2.sqrt() / T::from(2.0),
+			)
+		};
+		// Per-node values are summed in node order to stay deterministic
+		#[cfg(feature = "parallel")]
+		let sums: Vec<(T, T)> = self
+			.nodes
+			.par_iter()
+			.zip(self.speeds.points.par_chunks(dimensions))
+			.zip(self.old_speeds.points.par_chunks(dimensions))
+			.map(swinging_traction)
+			.collect();
+		#[cfg(not(feature = "parallel"))]
+		let sums = self
+			.nodes
+			.iter()
+			.zip(self.speeds.iter())
+			.zip(self.old_speeds.iter())
+			.map(swinging_traction);
+		let (total_swinging, total_traction) = sums
+			.into_iter()
+			.fold((T::zero(), T::zero()), |(s, t), (ns, nt)| (s + ns, t + nt));
+		self.adjust_speed(total_swinging.clone(), total_traction);
+		self.swinging = total_swinging;
+
+		let settings = &self.settings;
+		let speed = &self.speed;
+		let displace = |node: &Node<T>, pos: &mut [T], node_speed: &[T], old_speed: &[T]| -> T {
+			if node.fixed {
+				return T::zero();
+			}
+			let mut swinging2 = T::zero();
+			for i in 0usize..dimensions {
+				swinging2 += (old_speed[i].clone() - node_speed[i].clone()).powi(2);
+			}
+			let swinging = node.mass.clone() * swinging2.sqrt();
+			let mut factor = settings.scaling_ratio.clone() * speed.clone()
+				/ (T::one() + (speed.clone() * swinging).sqrt());
+			if settings.prevent_overlapping.is_some() {
+				// Slow down and limit the displacement, as in Gephi
+				let df = util::norm(node_speed);
+				if df.is_zero() {
+					return T::zero();
+				}
+				factor = util::min(T::from(0.1) * factor * df.clone(), T::from(10.0)) / df;
+			}
+			for i in 0usize..dimensions {
+				pos[i] += factor.clone() * node_speed[i].clone(); // adaptive convergence
+			}
+			factor * util::norm(node_speed)
+		};
+		#[cfg(feature = "parallel")]
+		let displacements: Vec<T> = self
+			.nodes
+			.par_iter()
+			.zip(self.points.points.par_chunks_mut(dimensions))
+			.zip(self.speeds.points.par_chunks(dimensions))
+			.zip(self.old_speeds.points.par_chunks(dimensions))
+			.map(|(((node, pos), node_speed), old_speed)| {
+				displace(node, pos, node_speed, old_speed)
+			})
+			.collect();
+		#[cfg(not(feature = "parallel"))]
+		let displacements = self
+			.nodes
+			.iter()
+			.zip(self.points.iter_mut())
+			.zip(self.speeds.iter())
+			.zip(self.old_speeds.iter())
+			.map(|(((node, pos), node_speed), old_speed)| {
+				displace(node, pos, node_speed, old_speed)
+			});
+		self.displacement = displacements
+			.into_iter()
+			.fold(T::zero(), |total, displacement| total + displacement);
+	}
+
+	/// Updates the global speed from the graph's swinging and traction (Gephi's algorithm)
+	fn adjust_speed(&mut self, total_swinging: T, total_traction: T) {
+		let nb_nodes = T::from(self.nodes.len() as f32);
+		let estimated_optimal_jt = T::from(0.05) * nb_nodes.clone().sqrt();
+		let min_jt = estimated_optimal_jt.clone().sqrt();
+		let max_jt = T::from(10.0);
+		let mut jt = self.settings.jitter_tolerance.clone()
+			* util::max(
+				min_jt,
+				util::min(
+					max_jt,
+					estimated_optimal_jt * total_traction.clone() / nb_nodes.powi(2),
+				),
+			);
+
+		let min_speed_efficiency = T::from(0.05);
+		if total_traction.positive()
+			&& total_swinging.clone() / total_traction.clone() > T::from(2.0)
+		{
+			if self.speed_efficiency > min_speed_efficiency {
+				self.speed_efficiency *= T::from(0.5);
+			}
+			jt = util::max(jt, self.settings.jitter_tolerance.clone());
+		}
+
+		let max_rise = self.settings.max_speed_ratio.clone() * self.speed.clone();
+		if total_swinging.is_zero() {
+			self.speed += max_rise;
+			return;
+		}
+		let target_speed = jt.clone() * self.speed_efficiency.clone() * total_traction.clone()
+			/ total_swinging.clone();
+
+		if total_swinging > jt * total_traction {
+			if self.speed_efficiency > min_speed_efficiency {
+				self.speed_efficiency *= T::from(0.7);
+			}
+		} else if self.speed < T::from(1000.0) {
+			self.speed_efficiency *= T::from(1.3);
+		}
+
+		self.speed += util::min(target_speed - self.speed.clone(), max_rise);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_construction_errors() {
+		let positions = [[0.0, 0.0], [1.0, 1.0]];
+		let build = |edges: Vec<Edge>, weights: Option<Vec<f64>>, settings: Settings<f64>| {
+			Layout::<f64>::from_position_graph_weighted(
+				EdgeList {
+					edges,
+					weights,
+					types: None,
+				},
+				positions.iter().map(|pos| &pos[..]),
+				settings,
+			)
+			.err()
+		};
+		assert_eq!(build(vec![(0, 1)], None, Settings::default()), None);
+		assert_eq!(
+			build(vec![(0, 1), (1, 2)], None, Settings::default()),
+			Some(Error::NodeIndex {
+				edge: Some(1),
+				node: 2,
+				nb_nodes: 2
+			})
+		);
+		assert_eq!(
+			build(vec![(0, 1)], Some(vec![1.0, 2.0]), Settings::default()),
+			Some(Error::WeightCount {
+				expected: 1,
+				found: 2
+			})
+		);
+		assert_eq!(
+			build(vec![(0, 1)], Some(vec![-2.0]), Settings::default()),
+			Some(Error::InvalidWeight { edge: 0 })
+		);
+		assert_eq!(
+			build(vec![(0, 1)], Some(vec![f64::INFINITY]), Settings::default()),
+			Some(Error::InvalidWeight { edge: 0 })
+		);
+		assert_eq!(
+			build(
+				vec![(0, 1)],
+				None,
+				Settings {
+					dimensions: 3,
+					..Default::default()
+				}
+			),
+			Some(Error::DimensionMismatch {
+				node: 0,
+				expected: 3,
+				found: 2
+			})
+		);
+		assert_eq!(
+			build(
+				vec![(0, 1)],
+				None,
+				Settings {
+					dimensions: 0,
+					..Default::default()
+				}
+			),
+			Some(Error::ZeroDimensions)
+		);
+		assert!(matches!(
+			build(
+				vec![(0, 1)],
+				None,
+				Settings {
+					scaling_ratio: 0.0,
+					..Default::default()
+				}
+			),
+			Some(Error::InvalidSettings(_))
+		));
+		assert_eq!(
+			Layout::<f64>::from_position_graph(vec![], std::iter::empty(), Settings::default())
+				.err(),
+			Some(Error::EmptyGraph)
+		);
+	}
+
+	#[cfg(feature = "rand")]
+	#[test]
+	fn test_seeded() {
+		let build = |seed| {
+			let mut layout = Layout::<f64>::from_graph_seeded(
+				vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 4)],
+				5,
+				Settings::default(),
+				seed,
+			)
+			.unwrap();
+			for _ in 0..10 {
+				layout.iteration();
+			}
+			layout.points.points
+		};
+		assert_eq!(build(42), build(42));
+		assert_ne!(build(42), build(43));
+
+		// A cube placement gives the same positions as the constructor with the same generator
+		use rand::SeedableRng;
+		let edges = vec![(0, 1), (1, 2)];
+		let with_rng = Layout::<f64>::from_graph_with_rng(
+			edges.clone(),
+			3,
+			Settings::default(),
+			&mut rand::rngs::StdRng::seed_from_u64(7),
+		)
+		.unwrap();
+		let placed = Layout::<f64>::from_graph_with_placement(
+			edges,
+			3,
+			Settings::default(),
+			InitialPlacement::Cube(&mut rand::rngs::StdRng::seed_from_u64(7)),
+		)
+		.unwrap();
+		assert_eq!(with_rng.points.points, placed.points.points);
+	}
+
+	#[test]
+	fn test_placement() {
+		let layout = Layout::<f64>::from_graph_with_placement(
+			vec![(0, 1), (1, 2)],
+			3,
+			Settings::default(),
+			InitialPlacement::Radial { root: 1 },
+		)
+		.unwrap();
+		assert_eq!(layout.points.get(1), [0.0, 0.0]);
+		assert_eq!(layout.points.points.len(), 6);
+
+		let mut layout =
+			Layout::<f64>::from_graph_halton(vec![(0, 1), (1, 2)], 3, Settings::default(), 7)
+				.unwrap();
+		let other =
+			Layout::<f64>::from_graph_halton(vec![(0, 1), (1, 2)], 3, Settings::default(), 7)
+				.unwrap();
+		assert_eq!(layout.points.points, other.points.points);
+		layout.iteration();
+	}
+
+	#[test]
+	fn test_edge_types() {
+		let settings = Settings {
+			ka: 1.0,
+			edge_types: vec![
+				EdgeType {
+					ka: 3.0,
+					lin_log: false,
+				},
+				EdgeType {
+					ka: 1.0,
+					lin_log: true,
+				},
+			],
+			..Default::default()
+		};
+		let positions = [[0.0, 0.0], [2.0, 0.0], [-2.0, 0.0], [0.0, 2.0]];
+		let build = |types: Option<Vec<Option<usize>>>| {
+			Layout::<f64>::from_position_graph_weighted(
+				EdgeList {
+					edges: vec![(0, 1), (0, 2), (0, 3)],
+					types,
+					..Default::default()
+				},
+				positions.iter().map(|pos| &pos[..]),
+				settings.clone(),
+			)
+		};
+		assert_eq!(
+			build(Some(vec![Some(0)])).err(),
+			Some(Error::EdgeTypeCount {
+				expected: 3,
+				found: 1
+			})
+		);
+		assert_eq!(
+			build(Some(vec![None, Some(2), None])).err(),
+			Some(Error::UnknownEdgeType {
+				edge: 1,
+				edge_type: 2
+			})
+		);
+		let layout = build(Some(vec![Some(0), None, Some(1)])).unwrap();
+		assert_eq!(layout.edge_types(), Some(&[Some(0), None, Some(1)][..]));
+
+		let mut layout = build(None).unwrap();
+		assert_eq!(
+			layout.set_edge_types(vec![Some(0), Some(1)]),
+			Err(Error::EdgeTypeCount {
+				expected: 3,
+				found: 2
+			})
+		);
+		assert_eq!(
+			layout.set_edge_types(vec![Some(0), Some(1), Some(2)]),
+			Err(Error::UnknownEdgeType {
+				edge: 2,
+				edge_type: 2
+			})
+		);
+		assert_eq!(layout.edge_types(), None);
+		layout.set_edge_type(1, Some(1)).unwrap();
+		assert_eq!(layout.edge_types(), Some(&[None, Some(1), None][..]));
+
+		// Untyped edges keep the global settings
+		layout.init_iteration();
+		layout.apply_attraction();
+		assert_eq!(layout.speeds.get(1), [-2.0, 0.0]);
+		assert!((layout.speeds.get(2)[0] - 3.0f64.ln()).abs() < 1e-12);
+		assert_eq!(layout.speeds.get(3), [0.0, -2.0]);
+
+		layout.set_edge_type(0, Some(0)).unwrap();
+		layout.init_iteration();
+		layout.apply_attraction();
+		assert_eq!(layout.speeds.get(1), [-6.0, 0.0]);
+		assert_eq!(layout.speeds.get(3), [0.0, -2.0]);
 
-					let f = T::from(
-						(unsafe { self.nodes.get_unchecked(n1) }.degree + 1)
-							* (unsafe { self.nodes.get_unchecked(n2) }.degree + 1),
-					) / d2 * self.settings.kr.clone();
+		layout.add_edge((1, 2), None).unwrap();
+		assert_eq!(
+			layout.edge_types(),
+			Some(&[Some(0), Some(1), None, None][..])
+		);
+		layout.remove_edge(0).unwrap();
+		assert_eq!(layout.edge_types(), Some(&[Some(1), None, None][..]));
+		layout.set_edge_type(0, None).unwrap();
+		assert_eq!(layout.edge_types(), Some(&[None, None, None][..]));
 
-					let n1_speed = self.speeds.get_mut(n1);
-					for i in 0usize..self.settings.dimensions {
-						n1_speed[i] -= f.clone() * di[i].clone();
-					}
-					let n2_speed = self.speeds.get_mut(n2);
-					for i in 0usize..self.settings.dimensions {
-						n2_speed[i] += f.clone() * di[i].clone();
-					}
-				}
-			}
-		}
+		// Duplicate edges are only merged within a type
+		let layout = Layout::<f64>::from_position_graph_weighted(
+			EdgeList {
+				edges: vec![(0, 1), (0, 1), (0, 1)],
+				types: Some(vec![Some(1), None, Some(1)]),
+				..Default::default()
+			},
+			positions.iter().map(|pos| &pos[..]),
+			Settings {
+				normalization: Normalization {
+					merge_duplicates: true,
+					..Default::default()
+				},
+				..settings.clone()
+			},
+		)
+		.unwrap();
+		assert_eq!(layout.edges, vec![(0, 1), (0, 1)]);
+		assert_eq!(layout.weights, Some(vec![2.0, 1.0]));
+		assert_eq!(layout.edge_types(), Some(&[Some(1), None][..]));
 	}
 
-	fn apply_forces(&mut self) {
-		for (node, pos, old_speed, speed) in izip!(
-			self.nodes.iter(),
-			self.points.iter_mut(),
-			self.speeds.iter(),
-			self.old_speeds.iter(),
-		) {
-			let mut swinging2 = T::zero(); // measure oscillations
-			let mut traction2 = T::zero(); // measure continuity
-			for i in 0usize..self.settings.dimensions {
-				// Note: consider expecting T which is Copy
-				swinging2 += (old_speed[i].clone() - speed[i].clone()).pow_n(2u32);
-				traction2 += (old_speed[i].clone() + speed[i].clone()).pow_n(2u32);
-			}
-			let factor = self.settings.scaling_ratio.clone() * (T::one() + traction2.sqrt()).ln()
-				/ (T::one() + swinging2.sqrt().sqrt())
-				/ T::from(node.degree + 1);
-			for i in 0usize..self.settings.dimensions {
-				pos[i] += factor.clone() * speed[i].clone(); // adaptive convergence
-			}
-		}
+	#[test]
+	fn test_groups() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			[[0.0, 0.0], [2.0, 0.0]].iter().map(|pos| &pos[..]),
+			Settings {
+				intra_group_attraction: 3.0,
+				inter_group_attraction: 0.5,
+				inter_group_repulsion: 2.0,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		let mut forces = |groups: [Option<usize>; 2]| {
+			layout.nodes[0].group = groups[0];
+			layout.nodes[1].group = groups[1];
+			layout.init_iteration();
+			layout.apply_attraction();
+			let attraction = layout.speeds.get(0)[0];
+			layout.init_iteration();
+			layout.apply_repulsion();
+			(attraction, layout.speeds.get(0)[0])
+		};
+		let (attraction, repulsion) = forces([None, Some(1)]);
+		assert_eq!(forces([Some(1), Some(1)]), (attraction * 3.0, repulsion));
+		assert_eq!(
+			forces([Some(0), Some(1)]),
+			(attraction * 0.5, repulsion * 2.0)
+		);
 	}
-}
 
-#[cfg(feature = "barnes_hut")]
-impl Layout<f64> {
-	#[inline]
-	fn inner_apply_repulsion_barnes_hut(&mut self) {
-		match self.settings.dimensions {
-			2 => {
-				let particles: Vec<nbody_barnes_hut::particle_2d::Particle2D> = self
-					.points
-					.iter()
-					.zip(self.nodes.iter())
-					.map(|(point, node)| nbody_barnes_hut::particle_2d::Particle2D {
-						position: nbody_barnes_hut::vector_2d::Vector2D {
-							x: point[0],
-							y: point[1],
-						},
-						mass: (node.degree + 1) as f64,
-					})
-					.collect();
-				let tree = nbody_barnes_hut::barnes_hut_2d::QuadTree::new(
-					&particles
-						.iter()
-						.collect::<Vec<&nbody_barnes_hut::particle_2d::Particle2D>>(),
-					self.settings.barnes_hut.unwrap(),
-				);
-				let kr = self.settings.kr.clone();
-				if let Some((node_size, krprime)) = &self.settings.prevent_overlapping {
-					izip!(
-						particles.into_iter(),
-						self.speeds.iter_mut(),
-						self.nodes.iter()
-					)
-					.for_each(|(particle, speed, node)| {
-						let nbody_barnes_hut::vector_2d::Vector2D { x, y } = tree
-							.calc_forces_on_particle(
-								particle.position,
-								node.degree + 1,
-								|d2, m1, dv, m2| {
-									let d = d2.sqrt();
-									let dprime = d - node_size;
-									(if dprime.positive() {
-										kr / dprime
-									} else if dprime.is_zero() {
-										return nbody_barnes_hut::vector_2d::Vector2D {
-											x: 0.0,
-											y: 0.0,
-										};
-									} else {
-										*krprime
-									}) * m1 * m2 as f64 / d * dv
-								},
-							);
-						speed[0] -= x;
-						speed[1] -= y;
-					});
-				} else {
-					izip!(
-						particles.into_iter(),
-						self.speeds.iter_mut(),
-						self.nodes.iter()
-					)
-					.for_each(|(particle, speed, node)| {
-						let nbody_barnes_hut::vector_2d::Vector2D { x, y } = tree
-							.calc_forces_on_particle(
-								particle.position,
-								node.degree + 1,
-								|d2, m1, dv, m2| m2 as f64 * m1 / d2.sqrt() * kr * dv,
-							);
-						speed[0] -= x;
-						speed[1] -= y;
-					});
-				}
-			}
-			3 => {
-				let particles: Vec<nbody_barnes_hut::particle_3d::Particle3D> = self
-					.points
-					.iter()
-					.zip(self.nodes.iter())
-					.map(|(point, node)| nbody_barnes_hut::particle_3d::Particle3D {
-						position: nbody_barnes_hut::vector_3d::Vector3D {
-							x: point[0],
-							y: point[1],
-							z: point[2],
-						},
-						mass: (node.degree + 1) as f64,
-					})
-					.collect();
-				let tree = nbody_barnes_hut::barnes_hut_3d::OctTree::new(
-					&particles
-						.iter()
-						.collect::<Vec<&nbody_barnes_hut::particle_3d::Particle3D>>(),
-					self.settings.barnes_hut.unwrap(),
-				);
-				let kr = self.settings.kr.clone();
-				if let Some((node_size, krprime)) = &self.settings.prevent_overlapping {
-					izip!(
-						particles.into_iter(),
-						self.speeds.iter_mut(),
-						self.nodes.iter()
-					)
-					.for_each(|(particle, speed, node)| {
-						let nbody_barnes_hut::vector_3d::Vector3D { x, y, z } = tree
-							.calc_forces_on_particle(
-								particle.position,
-								node.degree + 1,
-								|d2, m1, dv, m2| {
-									let d = d2.sqrt();
-									let dprime = d - node_size;
-									(if dprime.positive() {
-										kr / dprime
-									} else if dprime.is_zero() {
-										return nbody_barnes_hut::vector_3d::Vector3D {
-											x: 0.0,
-											y: 0.0,
-											z: 0.0,
-										};
-									} else {
-										*krprime
-									}) * m1 * m2 as f64 / d * dv
-								},
-							);
-						speed[0] -= x;
-						speed[1] -= y;
-						speed[2] -= z;
-					});
-				} else {
-					izip!(
-						particles.into_iter(),
-						self.speeds.iter_mut(),
-						self.nodes.iter()
+	#[test]
+	fn test_pack_components() {
+		let positions = [[0.0, 0.0], [1.0, 0.0], [0.5, 0.5], [0.0, 0.2], [2.0, 3.0]];
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (2, 3)],
+			positions.iter().map(|pos| &pos[..]),
+			Settings {
+				pack_components: Some(1.0),
+				..Default::default()
+			},
+		)
+		.unwrap();
+		for _ in 0..50 {
+			layout.iteration();
+		}
+		assert_eq!(layout.components, vec![0, 0, 1, 1, 2]);
+
+		// Bounding boxes (with margin) do not overlap
+		let assert_packed = |layout: &Layout<f64>, components: &[[usize; 2]], margin: f64| {
+			let boxes: Vec<(Vec<f64>, Vec<f64>)> = components
+				.iter()
+				.map(|[n1, n2]| {
+					let (p1, p2) = (layout.points.get(*n1), layout.points.get(*n2));
+					(
+						(0..2).map(|i| p1[i].min(p2[i]) - margin / 2.0).collect(),
+						(0..2).map(|i| p1[i].max(p2[i]) + margin / 2.0).collect(),
 					)
-					.for_each(|(particle, speed, node)| {
-						let nbody_barnes_hut::vector_3d::Vector3D { x, y, z } = tree
-							.calc_forces_on_particle(
-								particle.position,
-								node.degree + 1,
-								|d2, m1, dv, m2| m2 as f64 * m1 / d2.sqrt() * kr * dv,
-							);
-						speed[0] -= x;
-						speed[1] -= y;
-						speed[2] -= z;
-					});
+				})
+				.collect();
+			for (i, (min1, max1)) in boxes.iter().enumerate() {
+				for (min2, max2) in boxes[i + 1..].iter() {
+					assert!((0..2).any(|d| max1[d] < min2[d] || max2[d] < min1[d]));
 				}
 			}
-			_ => unimplemented!("Barnes-Hut only implemented for 2D and 3D"),
+		};
+		assert_packed(&layout, &[[0, 1], [2, 3], [4, 4]], 0.99);
+		// The arrangement is centered
+		let (min, max) = layout
+			.points
+			.iter()
+			.fold((f64::MAX, f64::MIN), |(min, max), pos| {
+				(min.min(pos[0]), max.max(pos[0]))
+			});
+		assert!((min + max).abs() < 1e-9);
+
+		// Movable components are packed beside the ones containing a fixed node
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (2, 3)],
+			positions[..4].iter().map(|pos| &pos[..]),
+			Settings {
+				pack_components: Some(0.5),
+				..Default::default()
+			},
+		)
+		.unwrap();
+		layout.nodes[0].fixed = true;
+		for _ in 0..50 {
+			layout.iteration();
 		}
+		assert_eq!(layout.points.get(0), [0.0, 0.0]);
+		assert_packed(&layout, &[[0, 1], [2, 3]], 0.49);
 	}
-}
 
-// This trait was needed to allow specialization
-#[doc(hidden)]
-pub trait Repulsion {
-	fn apply_repulsion(&mut self);
-}
+	#[test]
+	fn test_normalization() {
+		let edges = vec![(0, 1), (1, 1), (0, 1), (1, 0), (1, 2)];
+		let build = |weights: Option<Vec<f64>>, normalization: Normalization| {
+			Layout::<f64>::from_position_graph_weighted(
+				EdgeList {
+					edges: edges.clone(),
+					weights,
+					..Default::default()
+				},
+				[[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]
+					.iter()
+					.map(|pos| &pos[..]),
+				Settings {
+					normalization,
+					..Default::default()
+				},
+			)
+			.unwrap()
+		};
 
-#[cfg(not(feature = "barnes_hut"))]
-impl<T: Coord + std::fmt::Debug> Repulsion for Layout<T> {
-	fn apply_repulsion(&mut self) {
-		self.inner_apply_repulsion()
-	}
-}
+		let layout = build(None, Normalization::default());
+		assert_eq!(layout.edges, edges);
+		assert_eq!(layout.weights, None);
 
-#[cfg(feature = "barnes_hut")]
-default impl<T: Coord + std::fmt::Debug> Repulsion for Layout<T> {
-	fn apply_repulsion(&mut self) {
-		if self.settings.barnes_hut.is_none() {
-			self.inner_apply_repulsion()
-		} else {
-			unimplemented!("Barnes-Hut only implemented for Layout<f64>")
-		}
-	}
-}
+		let layout = build(
+			None,
+			Normalization {
+				remove_self_loops: true,
+				..Default::default()
+			},
+		);
+		assert_eq!(layout.edges, vec![(0, 1), (0, 1), (1, 0), (1, 2)]);
+		assert_eq!(layout.weights, None);
+		assert_eq!(layout.nodes[1].degree, 4);
 
-#[cfg(feature = "barnes_hut")]
-impl Repulsion for Layout<f64> {
-	fn apply_repulsion(&mut self) {
-		if self.settings.barnes_hut.is_some() {
-			self.inner_apply_repulsion_barnes_hut()
-		} else {
-			self.inner_apply_repulsion()
-		}
+		let layout = build(
+			None,
+			Normalization {
+				remove_self_loops: true,
+				merge_duplicates: true,
+				undirected: false,
+			},
+		);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 0), (1, 2)]);
+		assert_eq!(layout.weights, Some(vec![2.0, 1.0, 1.0]));
+
+		let layout = build(
+			Some(vec![1.0, 5.0, 2.0, 3.0, 4.0]),
+			Normalization {
+				remove_self_loops: false,
+				merge_duplicates: true,
+				undirected: true,
+			},
+		);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 1), (1, 2)]);
+		assert_eq!(layout.weights, Some(vec![6.0, 5.0, 4.0]));
+		assert_eq!(layout.nodes[0].degree, 1);
+		assert_eq!(layout.nodes[1].degree, 4);
 	}
-}
 
-#[cfg(test)]
-mod tests {
-	use super::*;
+	#[test]
+	fn test_graph_mutation() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (1, 2)],
+			[[0.0, 0.0], [2.0, 0.0], [2.0, 2.0]]
+				.iter()
+				.map(|pos| &pos[..]),
+			Settings::default(),
+		)
+		.unwrap();
+
+		assert_eq!(layout.add_node(&[0, 2]).unwrap(), 3);
+		assert_eq!(layout.points.get(3), [1.0, 1.0]);
+		assert_eq!(layout.edges, vec![(0, 1), (1, 2), (3, 0), (3, 2)]);
+		assert_eq!(layout.nodes[3].degree, 2);
+		assert_eq!(layout.nodes[0].mass, 3.0);
+		assert_eq!(layout.speeds.points.len(), 8);
+		assert_eq!(layout.old_speeds.points.len(), 8);
+
+		assert_eq!(layout.add_edge((0, 2), Some(3.0)).unwrap(), 4);
+		assert_eq!(layout.weights, Some(vec![1.0, 1.0, 1.0, 1.0, 3.0]));
+		assert_eq!(layout.remove_edge(1).unwrap(), (1, 2));
+		assert_eq!(layout.weights, Some(vec![1.0, 1.0, 1.0, 3.0]));
+		assert_eq!(layout.nodes[1].degree, 1);
+
+		// Node 3 takes the place of node 0
+		layout.remove_node(0).unwrap();
+		assert_eq!(layout.edges, vec![(0, 2)]);
+		assert_eq!(layout.weights, Some(vec![1.0]));
+		assert_eq!(layout.points.get(0), [1.0, 1.0]);
+		assert_eq!(layout.nodes.len(), 3);
+		assert_eq!(
+			layout
+				.nodes
+				.iter()
+				.map(|node| node.degree)
+				.collect::<Vec<u32>>(),
+			vec![1, 0, 1]
+		);
+		assert_eq!(layout.speeds.points.len(), 6);
+
+		assert_eq!(
+			layout.add_edge((0, 2), Some(f64::NAN)),
+			Err(Error::InvalidWeight { edge: 1 })
+		);
+		assert_eq!(
+			layout.add_edge((0, 3), None),
+			Err(Error::NodeIndex {
+				edge: Some(1),
+				node: 3,
+				nb_nodes: 3
+			})
+		);
+		assert_eq!(
+			layout.remove_edge(1),
+			Err(Error::EdgeIndex {
+				edge: 1,
+				nb_edges: 1
+			})
+		);
+		layout.iteration();
+
+		layout.remove_node(2).unwrap();
+		layout.remove_node(1).unwrap();
+		assert_eq!(layout.remove_node(0), Err(Error::EmptyGraph));
+		assert_eq!(layout.nodes.len(), 1);
+
+		// A node superposed with its neighbour is shifted by a hundredth of the mean edge length
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			[[0.0, 0.0], [300.0, 0.0]].iter().map(|pos| &pos[..]),
+			Settings::default(),
+		)
+		.unwrap();
+		assert_eq!(layout.add_node(&[0]).unwrap(), 2);
+		assert_eq!(layout.points.get(2), [3.0, 0.0]);
+	}
 
 	#[cfg(feature = "rand")]
 	#[test]
@@ -706,7 +2241,8 @@ mod tests {
 			vec![(0, 1), (0, 2), (0, 3), (1, 2), (1, 4)],
 			5,
 			Settings::default(),
-		);
+		)
+		.unwrap();
 
 		for _ in 0..10 {
 			layout.iteration();
@@ -721,15 +2257,30 @@ mod tests {
 			vec![(0, 1)],
 			vec![vec![-1.0, -1.0].as_slice(), vec![1.0, 1.0].as_slice()].into_iter(),
 			Settings::default(),
-		);
+		)
+		.unwrap();
 		layout
 			.speeds
 			.points
 			.iter_mut()
 			.enumerate()
 			.for_each(|(i, s)| *s += i as f64);
+		layout.old_speeds = layout.speeds.clone();
+		// No swinging: the global speed rises by `max_speed_ratio`
+		layout.apply_forces();
+		assert_eq!(layout.speed, 1.5);
+		assert_eq!(layout.points.points, vec![-1.0, 0.5, 4.0, 5.5]);
+
+		// Oscillation: the global speed decreases
+		layout.init_iteration();
+		layout
+			.speeds
+			.points
+			.iter_mut()
+			.zip(layout.old_speeds.points.iter())
+			.for_each(|(s, old_s)| *s = -old_s);
 		layout.apply_forces();
-		assert_eq!(layout.points.points, vec![-1.0, 0.0, 3.0, 4.0]);
+		assert!(layout.speed < 1.5);
 	}
 
 	#[test]
@@ -738,7 +2289,8 @@ mod tests {
 			vec![(0, 1)],
 			vec![vec![-1.0, -1.0].as_slice(), vec![1.0, 1.0].as_slice()].into_iter(),
 			Settings::default(),
-		);
+		)
+		.unwrap();
 		layout
 			.speeds
 			.points
@@ -755,7 +2307,8 @@ mod tests {
 			vec![(0, 1)],
 			vec![vec![-1.0, -1.0].as_slice(), vec![1.0, 1.0].as_slice()].into_iter(),
 			Settings::default(),
-		);
+		)
+		.unwrap();
 
 		layout.init_iteration();
 		layout.apply_attraction();
@@ -791,6 +2344,303 @@ mod tests {
 		assert!(speed_2[1] < 0.0);
 	}
 
+	#[test]
+	fn test_attraction_modes() {
+		// Node 0 has degree 2, the edge (0, 1) has weight 2 and length 5
+		let force = |prevent_overlapping: bool, lin_log: bool, dissuade_hubs: bool| {
+			let mut layout = Layout::<f64>::from_position_graph_weighted(
+				EdgeList {
+					edges: vec![(0, 1), (0, 2)],
+					weights: Some(vec![2.0, 1.0]),
+					..Default::default()
+				},
+				[[0.0, 0.0], [3.0, 4.0], [-1.0, 0.0]]
+					.iter()
+					.map(|pos| &pos[..]),
+				Settings {
+					ka: 0.5,
+					lin_log,
+					dissuade_hubs,
+					prevent_overlapping: if prevent_overlapping {
+						Some(100.0)
+					} else {
+						None
+					},
+					..Default::default()
+				},
+			)
+			.unwrap();
+			for node in layout.nodes.iter_mut() {
+				node.size = 0.5;
+			}
+			layout.init_iteration();
+			layout.apply_attraction();
+			let speed = layout.speeds.get(1);
+			assert!((speed[0] * 4.0 - speed[1] * 3.0).abs() < 1e-12);
+			-speed[0] / 3.0
+		};
+		// Gephi's formulas, `d'` being the distance minus the sizes
+		let (d, dprime, ka_w, degree) = (5.0f64, 4.0f64, 1.0, 2.0);
+		let expected = [
+			(false, false, false, ka_w),
+			(false, false, true, ka_w / degree),
+			(false, true, false, d.ln_1p() / d * ka_w),
+			(false, true, true, d.ln_1p() / d / degree * ka_w),
+			(true, false, false, dprime / d * ka_w),
+			(true, false, true, dprime / d / degree * ka_w),
+			(true, true, false, dprime.ln_1p() / dprime * ka_w),
+			(true, true, true, dprime.ln_1p() / dprime / degree * ka_w),
+		];
+		for (prevent_overlapping, lin_log, dissuade_hubs, f) in expected.iter() {
+			let found = force(*prevent_overlapping, *lin_log, *dissuade_hubs);
+			assert!(
+				(found - f).abs() < 1e-12,
+				"{} {} {}: {} {}",
+				prevent_overlapping,
+				lin_log,
+				dissuade_hubs,
+				found,
+				f
+			);
+		}
+	}
+
+	#[test]
+	fn test_edge_weights() {
+		let positions = [[0.0, 0.0], [1.0, 0.0], [0.0, 0.0], [1.0, 0.0]];
+		let mut layout = Layout::<f64>::from_position_graph_weighted(
+			EdgeList {
+				edges: vec![(0, 1), (2, 3)],
+				weights: Some(vec![1.0, 4.0]),
+				..Default::default()
+			},
+			positions.iter().map(|pos| pos.as_slice()),
+			Settings {
+				mass: Mass::WeightedDegree,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		assert_eq!(layout.nodes[0].mass, 2.0);
+		assert_eq!(layout.nodes[2].mass, 5.0);
+
+		layout.init_iteration();
+		layout.apply_attraction();
+		assert_eq!(layout.speeds.get(2)[0], 4.0 * layout.speeds.get(0)[0]);
+
+		layout.settings.edge_weight_influence = 0.5;
+		layout.init_iteration();
+		layout.apply_attraction();
+		assert!((layout.speeds.get(2)[0] - 2.0 * layout.speeds.get(0)[0]).abs() < 1e-12);
+
+		layout.settings.edge_weight_influence = 0.0;
+		layout.init_iteration();
+		layout.apply_attraction();
+		assert_eq!(layout.speeds.get(2)[0], layout.speeds.get(0)[0]);
+	}
+
+	#[test]
+	fn test_mass() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1), (0, 2), (1, 2)],
+			vec![
+				vec![0.0, 0.0].as_slice(),
+				vec![1.0, 0.0].as_slice(),
+				vec![0.0, 1.0].as_slice(),
+			]
+			.into_iter(),
+			Settings::default(),
+		)
+		.unwrap();
+		assert_eq!(
+			layout.nodes.iter().map(|n| n.mass).collect::<Vec<f64>>(),
+			vec![3.0, 3.0, 3.0]
+		);
+
+		layout.settings.mass = Mass::InDegree;
+		layout.update_masses();
+		assert_eq!(
+			layout.nodes.iter().map(|n| n.mass).collect::<Vec<f64>>(),
+			vec![1.0, 2.0, 3.0]
+		);
+
+		layout.settings.mass =
+			Mass::Custom(Arc::new(|n, node| (node.degree as f64).ln() + n as f64));
+		layout.update_masses();
+		assert_eq!(
+			layout.nodes.iter().map(|n| n.mass).collect::<Vec<f64>>(),
+			vec![2f64.ln(), 2f64.ln() + 1.0, 2f64.ln() + 2.0]
+		);
+	}
+
+	#[test]
+	fn test_incremental_masses() {
+		let masses: [Mass<f64>; 4] = [
+			Mass::Degree,
+			Mass::WeightedDegree,
+			Mass::InDegree,
+			Mass::Custom(Arc::new(|n, node| node.degree as f64 * 0.5 + n as f64)),
+		];
+		for mass in masses.iter().cloned() {
+			let positions = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+			let mut layout = Layout::<f64>::from_position_graph_weighted(
+				EdgeList {
+					edges: vec![(0, 1), (1, 2), (2, 3), (3, 3)],
+					weights: Some(vec![2.0, 0.5, 1.0, 3.0]),
+					..Default::default()
+				},
+				positions.iter().map(|pos| pos.as_slice()),
+				Settings {
+					mass,
+					..Default::default()
+				},
+			)
+			.unwrap();
+			layout.add_node(&[0, 3]).unwrap();
+			layout.add_edge((4, 1), Some(1.5)).unwrap();
+			layout.remove_edge(1).unwrap();
+			layout.remove_node(3).unwrap();
+			layout.add_edge((0, 0), None).unwrap();
+			let masses: Vec<f64> = layout.nodes.iter().map(|n| n.mass).collect();
+			layout.update_masses();
+			for (m1, m2) in masses.iter().zip(layout.nodes.iter()) {
+				assert!((m1 - m2.mass).abs() < 1e-12);
+			}
+		}
+	}
+
+	#[test]
+	fn test_node_sizes() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![],
+			vec![vec![0.0, 0.0].as_slice(), vec![1.0, 0.0].as_slice()].into_iter(),
+			Settings {
+				prevent_overlapping: Some(100.0),
+				..Default::default()
+			},
+		)
+		.unwrap();
+		layout.nodes[0].size = 0.2;
+		layout.nodes[1].size = 0.3;
+		layout.init_iteration();
+		layout.apply_repulsion();
+		assert!((layout.speeds.get(1)[0] - 2.0).abs() < 1e-12);
+
+		layout.nodes[1].size = 0.9;
+		layout.init_iteration();
+		layout.apply_repulsion();
+		assert_eq!(layout.speeds.get(0)[0], -100.0);
+		assert_eq!(layout.speeds.get(1)[0], 100.0);
+	}
+
+	#[test]
+	fn test_fixed_nodes() {
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![(0, 1)],
+			vec![vec![-1.0, -1.0].as_slice(), vec![1.0, 1.0].as_slice()].into_iter(),
+			Settings::default(),
+		)
+		.unwrap();
+		layout.nodes[0].fixed = true;
+		for _ in 0..10 {
+			layout.iteration();
+		}
+		assert_eq!(layout.points.get(0), [-1.0, -1.0]);
+		assert_ne!(layout.points.get(1), [1.0, 1.0]);
+	}
+
+	#[test]
+	fn test_run_until_converged() {
+		let build = || {
+			Layout::<f64>::from_position_graph(
+				vec![(0, 1), (1, 2), (2, 0), (2, 3)],
+				vec![
+					vec![-1.0, -1.0].as_slice(),
+					vec![1.0, 0.5].as_slice(),
+					vec![0.5, 1.0].as_slice(),
+					vec![-0.5, 1.5].as_slice(),
+				]
+				.into_iter(),
+				Settings::default(),
+			)
+			.unwrap()
+		};
+
+		// Displacement only
+		let mut layout = build();
+		let report = layout.run_until_converged(
+			&Tolerance {
+				displacement: 0.001,
+				swinging: 0.0,
+			},
+			1000,
+		);
+		assert!(report.converged);
+		assert!(report.iterations < 1000);
+		assert_eq!(report.displacements.len(), report.iterations);
+		assert!(*report.displacements.last().unwrap() < 0.004);
+
+		// Swinging only
+		let mut layout = build();
+		let report = layout.run_until_converged(
+			&Tolerance {
+				displacement: 0.0,
+				swinging: 0.3,
+			},
+			1000,
+		);
+		assert!(report.converged);
+		assert!(report.iterations < 1000);
+		assert!(report.iterations > 1);
+		assert!(layout.swinging < 4.0 * 0.3);
+
+		let report = layout.run_until_converged(
+			&Tolerance {
+				displacement: 0.0,
+				swinging: 0.0,
+			},
+			10,
+		);
+		assert!(!report.converged);
+		assert_eq!(report.iterations, 10);
+	}
+
+	#[cfg(feature = "parallel")]
+	#[test]
+	fn test_pair_blocks() {
+		assert_eq!(pair_blocks(1, 32), vec![0, 1]);
+		assert_eq!(pair_blocks(5, 1), vec![0, 5]);
+		// 45 pairs: rows 0..2 have 17, rows 2..4 have 13, rows 4..10 have 15
+		assert_eq!(pair_blocks(10, 3), vec![0, 2, 4, 10]);
+	}
+
+	#[cfg(feature = "parallel")]
+	#[test]
+	fn test_parallel_determinism() {
+		let run = |threads| {
+			let positions: Vec<Vec<f64>> = (0..50)
+				.map(|i| vec![(i as f64 * 0.7).sin(), (i as f64 * 1.3).cos()])
+				.collect();
+			let mut layout = Layout::<f64>::from_position_graph(
+				(0..50).map(|i| (i, (i * 7 + 3) % 50)).collect(),
+				positions.iter().map(|pos| pos.as_slice()),
+				Settings::default(),
+			)
+			.unwrap();
+			rayon::ThreadPoolBuilder::new()
+				.num_threads(threads)
+				.build()
+				.unwrap()
+				.install(|| {
+					for _ in 0..20 {
+						layout.iteration();
+					}
+				});
+			layout.points.points
+		};
+		assert_eq!(run(1), run(4));
+	}
+
 	#[cfg(feature = "barnes_hut")]
 	#[test]
 	fn test_barnes_hut_2d() {
@@ -798,7 +2648,8 @@ mod tests {
 			vec![(0, 1)],
 			vec![vec![-1.0, -1.0].as_slice(), vec![1.0, 1.0].as_slice()].into_iter(),
 			Settings::default(),
-		);
+		)
+		.unwrap();
 
 		layout.settings.barnes_hut = Some(0.5);
 		layout.init_iteration();
@@ -813,6 +2664,87 @@ mod tests {
 		assert!(speed_2[1] > 0.0);
 	}
 
+	#[cfg(feature = "barnes_hut")]
+	#[test]
+	fn test_barnes_hut_nd() {
+		let positions: Vec<Vec<f32>> = (0..40)
+			.map(|i| (0..4).map(|j| ((i * 4 + j) as f32 * 0.37).sin()).collect())
+			.collect();
+		let mut layout = Layout::<f32>::from_position_graph(
+			(0..40).map(|i| (i, (i * 3 + 1) % 40)).collect(),
+			positions.iter().map(|pos| pos.as_slice()),
+			Settings {
+				dimensions: 4,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		layout.init_iteration();
+		layout.apply_repulsion();
+		let exact = layout.speeds.points.clone();
+
+		// theta = 0 never approximates
+		layout.settings.barnes_hut = Some(0.0);
+		layout.init_iteration();
+		layout.apply_repulsion();
+		for (bh, exact) in layout.speeds.points.iter().zip(exact.iter()) {
+			assert!((bh - exact).abs() <= 1e-3 * exact.abs().max(1.0));
+		}
+
+		layout.settings.barnes_hut = Some(0.5);
+		layout.init_iteration();
+		layout.apply_repulsion();
+		let error: f32 = layout
+			.speeds
+			.points
+			.iter()
+			.zip(exact.iter())
+			.map(|(bh, exact)| (bh - exact).powi(2))
+			.sum();
+		let norm: f32 = exact.iter().map(|exact| exact.powi(2)).sum();
+		assert!(error < 0.01 * norm);
+	}
+
+	#[cfg(feature = "barnes_hut")]
+	#[test]
+	fn test_barnes_hut_groups() {
+		// Two distant groups, each one approximated as a cluster by the nodes of the other
+		let positions: Vec<Vec<f64>> = (0..40)
+			.map(|i| {
+				let x = if i % 2 == 0 { 0.0 } else { 20.0 };
+				vec![x + (i as f64 * 0.37).sin(), (i as f64 * 0.73).cos()]
+			})
+			.collect();
+		let mut layout = Layout::<f64>::from_position_graph(
+			vec![],
+			positions.iter().map(|pos| pos.as_slice()),
+			Settings {
+				inter_group_repulsion: 4.0,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		for (n, node) in layout.nodes.iter_mut().enumerate() {
+			node.group = Some(n % 2);
+		}
+		layout.init_iteration();
+		layout.apply_repulsion();
+		let exact = layout.speeds.points.clone();
+
+		layout.settings.barnes_hut = Some(0.5);
+		layout.init_iteration();
+		layout.apply_repulsion();
+		let error: f64 = layout
+			.speeds
+			.points
+			.iter()
+			.zip(exact.iter())
+			.map(|(bh, exact)| (bh - exact).powi(2))
+			.sum();
+		let norm: f64 = exact.iter().map(|exact| exact.powi(2)).sum();
+		assert!(error < 0.01 * norm);
+	}
+
 	#[test]
 	fn test_convergence() {
 		let mut layout = Layout::<f64>::from_position_graph(
@@ -825,17 +2757,13 @@ mod tests {
 			.into_iter(),
 			Settings {
 				dimensions: 2,
-				dissuade_hubs: false,
 				ka: 0.5,
 				kg: 0.01,
 				kr: 0.01,
-				lin_log: false,
-				prevent_overlapping: None,
-				strong_gravity: false,
-				#[cfg(feature = "barnes_hut")]
-				barnes_hut: None,
+				..Default::default()
 			},
-		);
+		)
+		.unwrap();
 
 		for _ in 0..10 {
 			println!("new iteration");
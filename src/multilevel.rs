@@ -0,0 +1,434 @@
+use crate::{util, Coord, Edge, EdgeList, Error, Layout, Normalization, Settings, Tolerance};
+
+use rand::{seq::SliceRandom, Rng};
+use std::collections::{hash_map::Entry, HashMap};
+
+/// Parameters of the multilevel scheme (see [`Layout::from_graph_multilevel`])
+#[derive(Clone)]
+pub struct Multilevel<T> {
+	/// Stop coarsening when the graph has at most this number of nodes
+	pub min_nodes: usize,
+	/// Maximum number of iterations at each level
+	pub iterations: usize,
+	/// Stopping criteria of [`Layout::run_until_converged`] at each level
+	pub tolerance: Tolerance<T>,
+}
+
+impl<T: Coord> Default for Multilevel<T> {
+	fn default() -> Self {
+		Self {
+			min_nodes: 100,
+			iterations: 100,
+			tolerance: Tolerance {
+				displacement: T::from(0.01),
+				..Default::default()
+			},
+		}
+	}
+}
+
+/// Graph at one level of coarsening
+struct Level<T> {
+	nb_nodes: usize,
+	edges: Vec<Edge>,
+	weights: Vec<T>,
+	/// Edge types (`None` if no edge is typed)
+	types: Option<Vec<Option<usize>>>,
+	/// Mass of each node (sum of the masses of the merged nodes)
+	masses: Vec<T>,
+}
+
+impl<T: Coord + std::fmt::Debug> Layout<T> {
+	/// Instanciates a layout from a directed graph, using a multilevel scheme
+	///
+	/// The graph is coarsened by matching neighbor nodes (heaviest edges first), until it has at most `multilevel.min_nodes` nodes.
+	/// The coarsest graph is laid out from random positions, then each level is initialized from the coarser one and refined,
+	/// until the original graph.
+	pub fn from_graph_multilevel(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		multilevel: &Multilevel<T>,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_multilevel_weighted(edges.into(), nb_nodes, settings, multilevel)
+	}
+
+	/// Same as [`Layout::from_graph_multilevel`], with edge weights (see [`EdgeList`])
+	pub fn from_graph_multilevel_weighted(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		multilevel: &Multilevel<T>,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_multilevel_with_rng_weighted(
+			graph,
+			nb_nodes,
+			settings,
+			multilevel,
+			&mut rand::thread_rng(),
+		)
+	}
+
+	/// Same as [`Layout::from_graph_multilevel`], with randomness (matching and positions) from `rng`
+	pub fn from_graph_multilevel_with_rng<R: Rng>(
+		edges: Vec<Edge>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		multilevel: &Multilevel<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		Self::from_graph_multilevel_with_rng_weighted(
+			edges.into(),
+			nb_nodes,
+			settings,
+			multilevel,
+			rng,
+		)
+	}
+
+	/// Same as [`Layout::from_graph_multilevel_with_rng`], with edge weights (see [`EdgeList`])
+	pub fn from_graph_multilevel_with_rng_weighted<R: Rng>(
+		graph: EdgeList<T>,
+		nb_nodes: usize,
+		settings: Settings<T>,
+		multilevel: &Multilevel<T>,
+		rng: &mut R,
+	) -> Result<Self, Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let mut layout = Self::from_graph_with_rng_weighted(graph, nb_nodes, settings, rng)?;
+
+		let mut levels = vec![Level {
+			nb_nodes,
+			edges: layout.edges.clone(),
+			weights: layout
+				.weights
+				.clone()
+				.unwrap_or_else(|| layout.edges.iter().map(|_| T::one()).collect()),
+			types: layout.edge_types.clone(),
+			masses: layout.nodes.iter().map(|node| node.mass.clone()).collect(),
+		}];
+		// `parents[i][n]` is the node of level `i + 1` containing the node `n` of level `i`
+		let mut parents: Vec<Vec<usize>> = Vec::new();
+		while levels[levels.len() - 1].nb_nodes > multilevel.min_nodes {
+			let level = &levels[levels.len() - 1];
+			let (level_parents, coarse) = coarsen(level, rng);
+			// Stop when matching does not reduce the graph anymore (e.g. star graphs)
+			if coarse.nb_nodes * 10 > level.nb_nodes * 9 {
+				break;
+			}
+			parents.push(level_parents);
+			levels.push(coarse);
+		}
+		if parents.is_empty() {
+			layout.run_until_converged(&multilevel.tolerance, multilevel.iterations);
+			return Ok(layout);
+		}
+
+		// Coarse graphs are already normalized
+		let coarse_settings = Settings {
+			normalization: Normalization::default(),
+			..layout.settings.clone()
+		};
+		let coarsest = levels.pop().unwrap();
+		let mut coarse_layout = Self::from_graph_with_rng_weighted(
+			EdgeList {
+				edges: coarsest.edges,
+				weights: Some(coarsest.weights),
+				types: coarsest.types,
+			},
+			coarsest.nb_nodes,
+			coarse_settings.clone(),
+			rng,
+		)?;
+		set_masses(&mut coarse_layout, coarsest.masses);
+		coarse_layout.run_until_converged(&multilevel.tolerance, multilevel.iterations);
+		while let Some(level) = levels.pop() {
+			let points = prolong(&coarse_layout, &parents[levels.len()], rng);
+			if levels.is_empty() {
+				layout.points.points = points;
+				break;
+			}
+			coarse_layout = Self::from_points(
+				EdgeList {
+					edges: level.edges,
+					weights: Some(level.weights),
+					types: level.types,
+				},
+				points,
+				coarse_settings.clone(),
+			)?;
+			set_masses(&mut coarse_layout, level.masses);
+			coarse_layout.run_until_converged(&multilevel.tolerance, multilevel.iterations);
+		}
+		layout.run_until_converged(&multilevel.tolerance, multilevel.iterations);
+		Ok(layout)
+	}
+}
+
+/// Replaces the masses computed from a coarse graph by the masses of the merged nodes
+fn set_masses<T: Coord>(layout: &mut Layout<T>, masses: Vec<T>) {
+	for (node, mass) in layout.nodes.iter_mut().zip(masses) {
+		node.mass = mass;
+	}
+}
+
+/// Merges matched nodes, returning the coarse node of each node and the coarse graph
+///
+/// Nodes are visited in random order, and each one is matched with its free neighbor having the heaviest edge.
+/// Edges inside a coarse node are dropped, and parallel edges of the same type are merged by summing their weights.
+/// A coarse node's mass is the sum of its nodes' masses.
+fn coarsen<T: Coord, R: Rng>(level: &Level<T>, rng: &mut R) -> (Vec<usize>, Level<T>) {
+	let mut neighbors: Vec<Vec<(usize, usize)>> = (0..level.nb_nodes).map(|_| Vec::new()).collect();
+	for (e, (n1, n2)) in level.edges.iter().enumerate() {
+		if n1 != n2 {
+			neighbors[*n1].push((*n2, e));
+			neighbors[*n2].push((*n1, e));
+		}
+	}
+	let mut order: Vec<usize> = (0..level.nb_nodes).collect();
+	order.shuffle(rng);
+
+	let mut parents = vec![usize::MAX; level.nb_nodes];
+	let mut nb_nodes = 0;
+	for n in order.into_iter() {
+		if parents[n] != usize::MAX {
+			continue;
+		}
+		let mut mate: Option<(usize, &T)> = None;
+		for (m, e) in neighbors[n].iter() {
+			let heavier = match mate {
+				Some((_, w)) => level.weights[*e] > *w,
+				None => true,
+			};
+			if parents[*m] == usize::MAX && heavier {
+				mate = Some((*m, &level.weights[*e]));
+			}
+		}
+		parents[n] = nb_nodes;
+		if let Some((m, _)) = mate {
+			parents[m] = nb_nodes;
+		}
+		nb_nodes += 1;
+	}
+
+	let mut masses: Vec<T> = (0..nb_nodes).map(|_| T::zero()).collect();
+	for (parent, mass) in parents.iter().zip(level.masses.iter()) {
+		masses[*parent] += mass.clone();
+	}
+
+	let mut edges = Vec::new();
+	let mut weights: Vec<T> = Vec::new();
+	let mut types = Vec::new();
+	let mut indices: HashMap<(Edge, Option<usize>), usize> = HashMap::new();
+	for (e, ((n1, n2), w)) in level.edges.iter().zip(level.weights.iter()).enumerate() {
+		let (p1, p2) = (parents[*n1], parents[*n2]);
+		if p1 == p2 {
+			continue;
+		}
+		let edge_type = level.types.as_ref().and_then(|types| types[e]);
+		match indices.entry(((util::min(p1, p2), util::max(p1, p2)), edge_type)) {
+			Entry::Occupied(index) => weights[*index.get()] += w.clone(),
+			Entry::Vacant(index) => {
+				index.insert(edges.len());
+				edges.push((p1, p2));
+				weights.push(w.clone());
+				types.push(edge_type);
+			}
+		}
+	}
+	(
+		parents,
+		Level {
+			nb_nodes,
+			edges,
+			weights,
+			types: level.types.as_ref().map(|_| types),
+			masses,
+		},
+	)
+}
+
+/// Positions of the finer nodes: each one is placed near its coarse node
+///
+/// Matched nodes are slightly shifted at random (by a tenth of the mean edge length), so that they do not overlap.
+fn prolong<T, R: Rng>(coarse: &Layout<T>, parents: &[usize], rng: &mut R) -> Vec<T>
+where
+	rand::distributions::Standard: rand::distributions::Distribution<T>,
+	T: Coord + rand::distributions::uniform::SampleUniform,
+{
+	let radius = match util::mean_edge_length(&coarse.edges, |n| Some(coarse.points.get(n))) {
+		Some(length) => length / T::from(10.0),
+		None => T::from(0.1),
+	};
+	let mut points = Vec::with_capacity(parents.len() * coarse.settings.dimensions);
+	for parent in parents.iter() {
+		let jitter: Vec<T> = util::sample_unit_ncube(rng, coarse.settings.dimensions);
+		for (x, j) in coarse.points.get(*parent).iter().zip(jitter) {
+			points.push(x.clone() + j * radius.clone());
+		}
+	}
+	points
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_coarsen() {
+		let level = Level {
+			nb_nodes: 8,
+			edges: (0..7).map(|n| (n, n + 1)).collect(),
+			weights: vec![1.0; 7],
+			types: None,
+			masses: (0..8).map(|n| n as f64).collect(),
+		};
+		let (parents, coarse) = coarsen(&level, &mut rand::thread_rng());
+		assert!(coarse.nb_nodes >= 4 && coarse.nb_nodes <= 6);
+		for p in 0..coarse.nb_nodes {
+			let members: Vec<usize> = (0..8).filter(|n| parents[*n] == p).collect();
+			// Matched nodes are neighbors
+			match members.as_slice() {
+				[_] => {}
+				[n1, n2] => assert_eq!(n1 + 1, *n2),
+				_ => panic!("bad matching {:?}", members),
+			}
+			assert_eq!(
+				coarse.masses[p],
+				members.iter().map(|n| *n as f64).sum::<f64>()
+			);
+		}
+		// Total weight is kept, minus the collapsed edges
+		let collapsed = 8 - coarse.nb_nodes;
+		assert_eq!(coarse.weights.iter().sum::<f64>(), (7 - collapsed) as f64);
+	}
+
+	#[test]
+	fn test_multilevel() {
+		// 20x20 grid
+		let side = 20;
+		let mut edges = Vec::new();
+		for i in 0..side {
+			for j in 0..side {
+				if i + 1 < side {
+					edges.push((i * side + j, (i + 1) * side + j));
+				}
+				if j + 1 < side {
+					edges.push((i * side + j, i * side + j + 1));
+				}
+			}
+		}
+		let layout = Layout::<f64>::from_graph_multilevel(
+			edges.clone(),
+			side * side,
+			Settings::default(),
+			&Multilevel {
+				min_nodes: 10,
+				iterations: 20,
+				tolerance: Tolerance {
+					displacement: 0.01,
+					..Default::default()
+				},
+			},
+		)
+		.unwrap();
+		assert_eq!(layout.edges, edges);
+		assert_eq!(layout.weights, None);
+		assert_eq!(layout.points.points.len(), side * side * 2);
+		assert!(layout.points.points.iter().all(|x| x.is_finite()));
+	}
+
+	#[test]
+	fn test_multilevel_quality() {
+		use rand::{rngs::StdRng, SeedableRng};
+
+		// 16x16 grid
+		let side = 16;
+		let nb_nodes = side * side;
+		let mut edges = Vec::new();
+		for i in 0..side {
+			for j in 0..side {
+				if i + 1 < side {
+					edges.push((i * side + j, (i + 1) * side + j));
+				}
+				if j + 1 < side {
+					edges.push((i * side + j, i * side + j + 1));
+				}
+			}
+		}
+		// Mean edge length relative to the mean distance between nodes (low when the grid is unfolded)
+		let quality = |layout: &Layout<f64>| {
+			let dist = |n1: usize, n2: usize| {
+				let (p1, p2) = (layout.points.get(n1), layout.points.get(n2));
+				((p1[0] - p2[0]).powi(2) + (p1[1] - p2[1]).powi(2)).sqrt()
+			};
+			let edge_length = layout
+				.edges
+				.iter()
+				.map(|(n1, n2)| dist(*n1, *n2))
+				.sum::<f64>()
+				/ layout.edges.len() as f64;
+			let mut distance = 0.0;
+			for n1 in 0..nb_nodes {
+				for n2 in n1 + 1..nb_nodes {
+					distance += dist(n1, n2);
+				}
+			}
+			edge_length / (distance / (nb_nodes * (nb_nodes - 1) / 2) as f64)
+		};
+		let tolerance = Tolerance {
+			displacement: 0.0,
+			swinging: 0.0,
+		};
+		let iterations = 50;
+		let (mut multilevel_quality, mut flat_quality) = (0.0, 0.0);
+		for seed in 0..3 {
+			let layout = Layout::<f64>::from_graph_multilevel_with_rng(
+				edges.clone(),
+				nb_nodes,
+				Settings::default(),
+				&Multilevel {
+					min_nodes: 10,
+					iterations,
+					tolerance: tolerance.clone(),
+				},
+				&mut StdRng::seed_from_u64(seed),
+			)
+			.unwrap();
+			multilevel_quality += quality(&layout);
+
+			// Each coarse level has about half the nodes of the finer one,
+			// so all the levels cost less than twice as many iterations on the full graph.
+			let mut layout = Layout::<f64>::from_graph_with_rng(
+				edges.clone(),
+				nb_nodes,
+				Settings::default(),
+				&mut StdRng::seed_from_u64(seed),
+			)
+			.unwrap();
+			layout.run_until_converged(&tolerance, 2 * iterations);
+			flat_quality += quality(&layout);
+		}
+		assert!(
+			multilevel_quality < flat_quality,
+			"{} {}",
+			multilevel_quality,
+			flat_quality
+		);
+	}
+}
@@ -0,0 +1,175 @@
+use crate::{Coord, EdgeList, Error, IdMap, KeyedLayout, Layout, Settings};
+
+use petgraph::visit::{EdgeRef, IntoEdgeReferences, IntoNodeIdentifiers};
+use std::hash::Hash;
+
+impl<T: Coord + std::fmt::Debug, K: Clone + Eq + Hash> KeyedLayout<T, K> {
+	/// Instanciates a randomly positioned layout from a petgraph graph (e.g. `&Graph` or `&StableGraph`)
+	///
+	/// Nodes are keyed by their petgraph ids. Edge weights are ignored.
+	#[cfg(feature = "rand")]
+	pub fn from_petgraph<G>(graph: G, settings: Settings<T>) -> Result<Self, Error>
+	where
+		G: IntoNodeIdentifiers<NodeId = K> + IntoEdgeReferences,
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let (ids, graph) = index_petgraph(graph, None::<fn(&G::EdgeWeight) -> T>);
+		Ok(Self {
+			layout: Layout::from_graph_weighted(graph, ids.len(), settings)?,
+			ids,
+		})
+	}
+
+	/// Instanciates a randomly positioned layout from a petgraph graph, with edge weights computed by `weight`
+	///
+	/// Nodes are keyed by their petgraph ids.
+	#[cfg(feature = "rand")]
+	pub fn from_weighted_petgraph<G, F: FnMut(&G::EdgeWeight) -> T>(
+		graph: G,
+		weight: F,
+		settings: Settings<T>,
+	) -> Result<Self, Error>
+	where
+		G: IntoNodeIdentifiers<NodeId = K> + IntoEdgeReferences,
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		let (ids, graph) = index_petgraph(graph, Some(weight));
+		Ok(Self {
+			layout: Layout::from_graph_weighted(graph, ids.len(), settings)?,
+			ids,
+		})
+	}
+
+	/// Instanciates a quasi-randomly positioned layout from a petgraph graph (see [`Layout::from_graph_halton`])
+	///
+	/// Nodes are keyed by their petgraph ids. Edge weights are ignored.
+	pub fn from_petgraph_halton<G>(
+		graph: G,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error>
+	where
+		G: IntoNodeIdentifiers<NodeId = K> + IntoEdgeReferences,
+	{
+		let (ids, graph) = index_petgraph(graph, None::<fn(&G::EdgeWeight) -> T>);
+		Ok(Self {
+			layout: Layout::from_graph_halton_weighted(graph, ids.len(), settings, seed)?,
+			ids,
+		})
+	}
+
+	/// Instanciates a quasi-randomly positioned layout from a petgraph graph, with edge weights computed by `weight`
+	///
+	/// Nodes are keyed by their petgraph ids.
+	pub fn from_weighted_petgraph_halton<G, F: FnMut(&G::EdgeWeight) -> T>(
+		graph: G,
+		weight: F,
+		settings: Settings<T>,
+		seed: u64,
+	) -> Result<Self, Error>
+	where
+		G: IntoNodeIdentifiers<NodeId = K> + IntoEdgeReferences,
+	{
+		let (ids, graph) = index_petgraph(graph, Some(weight));
+		Ok(Self {
+			layout: Layout::from_graph_halton_weighted(graph, ids.len(), settings, seed)?,
+			ids,
+		})
+	}
+}
+
+/// Indexes nodes in petgraph order, and edges with their weights if `weight` is given
+fn index_petgraph<T, K, G, F>(graph: G, mut weight: Option<F>) -> (IdMap<K>, EdgeList<T>)
+where
+	K: Clone + Eq + Hash,
+	G: IntoNodeIdentifiers<NodeId = K> + IntoEdgeReferences,
+	F: FnMut(&G::EdgeWeight) -> T,
+{
+	let mut ids = IdMap::new();
+	for node in graph.node_identifiers() {
+		ids.insert(node);
+	}
+	let mut edges = Vec::new();
+	let mut weights = Vec::new();
+	for edge in graph.edge_references() {
+		edges.push((ids.insert(edge.source()), ids.insert(edge.target())));
+		if let Some(weight) = &mut weight {
+			weights.push(weight(edge.weight()));
+		}
+	}
+	(
+		ids,
+		EdgeList {
+			edges,
+			weights: weight.map(|_| weights),
+			types: None,
+		},
+	)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use petgraph::graph::Graph;
+
+	#[cfg(feature = "rand")]
+	#[test]
+	fn test_petgraph() {
+		let mut graph = Graph::<&str, f64>::new();
+		let a = graph.add_node("a");
+		let b = graph.add_node("b");
+		let c = graph.add_node("c");
+		let d = graph.add_node("d");
+		graph.add_edge(a, b, 2.0);
+		graph.add_edge(b, c, 3.0);
+
+		let mut layout =
+			KeyedLayout::<f64, _>::from_weighted_petgraph(&graph, |w| *w, Settings::default())
+				.unwrap();
+		assert_eq!(layout.nodes.len(), 4);
+		assert_eq!(layout.weights, Some(vec![2.0, 3.0]));
+		assert_eq!(layout.edges, vec![(0, 1), (1, 2)]);
+		layout.iteration();
+		let positions = layout.position_map();
+		assert_eq!(positions.len(), 4);
+		assert_eq!(&positions[&d][..], layout.position(&d).unwrap());
+
+		let mut graph = petgraph::stable_graph::StableGraph::<(), ()>::new();
+		let a = graph.add_node(());
+		let b = graph.add_node(());
+		let c = graph.add_node(());
+		graph.add_edge(a, c, ());
+		graph.remove_node(b);
+		let layout = KeyedLayout::<f64, _>::from_petgraph(&graph, Settings::default()).unwrap();
+		assert_eq!(layout.weights, None);
+		assert_eq!(layout.edges, vec![(0, 1)]);
+		assert_eq!(layout.ids().keys(), [a, c]);
+	}
+
+	#[test]
+	fn test_petgraph_halton() {
+		let mut graph = Graph::<(), f32>::new();
+		let a = graph.add_node(());
+		let b = graph.add_node(());
+		let c = graph.add_node(());
+		graph.add_edge(a, b, 0.5);
+		graph.add_edge(c, b, 1.5);
+
+		let layout =
+			KeyedLayout::<f64, _>::from_petgraph_halton(&graph, Settings::default(), 3).unwrap();
+		assert_eq!(layout.weights, None);
+		assert_eq!(layout.edges, vec![(0, 1), (2, 1)]);
+		let weighted = KeyedLayout::<f64, _>::from_weighted_petgraph_halton(
+			&graph,
+			|w| *w as f64,
+			Settings::default(),
+			3,
+		)
+		.unwrap();
+		assert_eq!(weighted.weights, Some(vec![0.5, 1.5]));
+		assert_eq!(weighted.position_map(), layout.position_map());
+	}
+}
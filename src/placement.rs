@@ -0,0 +1,629 @@
+#[cfg(feature = "rand")]
+use crate::util;
+use crate::{Coord, Edge, Error};
+
+#[cfg(feature = "rand")]
+use rand::RngCore;
+use std::collections::VecDeque;
+
+/// How the nodes are positioned before the first iteration
+///
+/// Positions are of the order of `1` (before the layout scales them).
+pub enum InitialPlacement<'a, T> {
+	/// Uniform random distribution in the cube `[-1, 1]^dimensions`
+	#[cfg(feature = "rand")]
+	Cube(&'a mut dyn RngCore),
+	/// Uniform random distribution on the unit n-sphere
+	#[cfg(feature = "rand")]
+	Sphere(&'a mut dyn RngCore),
+	/// Uniform random distribution in the unit n-ball
+	#[cfg(feature = "rand")]
+	Ball(&'a mut dyn RngCore),
+	/// Evenly spaced on the unit circle (on the first two dimensions)
+	Circle,
+	/// Halton low-discrepancy sequence in the cube `[-1, 1]^dimensions`
+	///
+	/// Looks random but covers the cube evenly, and does not need `rand`. The node `n` gets the element `seed + n + 1`
+	/// (wrapping around `u64::MAX`) of the sequence (one prime base per dimension), so a given seed always gives the same positions.
+	Halton { seed: u64 },
+	/// Regular grid filling the cube `[-1, 1]^dimensions`
+	Grid,
+	/// Rings around `root`: nodes at distance `k` from `root` (ignoring edge directions) are on the circle of radius `k`
+	///
+	/// Nodes unreachable from `root` are on the outermost ring.
+	Radial { root: usize },
+	/// Eigenvectors of the normalized Laplacian of the graph (ignoring edge directions), one per dimension
+	///
+	/// Each axis is the eigenvector of the next smallest non-trivial eigenvalue, computed by power iteration,
+	/// scaled to `[-1, 1]`. Connected nodes are close, so the layout starts almost untangled.
+	Spectral,
+	/// Pivot MDS: classical multidimensional scaling of the graph distances (ignoring edge directions) to `pivots` nodes
+	///
+	/// Pivots are spread by max-min distance. Runs in `O(pivots * (nodes + edges))`, so it suits graphs too large
+	/// for [`InitialPlacement::Spectral`], and gives a globally faithful shape. A few dozen pivots are usually enough.
+	/// Positions are scaled to fit in `[-1, 1]^dimensions`.
+	PivotMds { pivots: usize },
+	/// Function writing the position of the given node (initialized to zeros)
+	Custom(PlacementFn<'a, T>),
+}
+
+/// Function writing the position of the given node (see [`InitialPlacement::Custom`])
+pub type PlacementFn<'a, T> = Box<dyn FnMut(usize, &mut [T]) + 'a>;
+
+impl<'a, T: Coord> InitialPlacement<'a, T> {
+	/// Positions of `nb_nodes` nodes, concatenated
+	///
+	/// Edges referring to missing nodes are ignored.
+	pub fn place(
+		self,
+		nb_nodes: usize,
+		edges: &[Edge],
+		dimensions: usize,
+	) -> Result<Vec<T>, Error> {
+		let from_f32 = |v: Vec<f32>| v.into_iter().map(T::from);
+		let points = match self {
+			#[cfg(feature = "rand")]
+			InitialPlacement::Cube(rng) => (0..nb_nodes)
+				.flat_map(|_| util::sample_unit_ncube::<T, _>(rng, dimensions))
+				.collect(),
+			#[cfg(feature = "rand")]
+			InitialPlacement::Sphere(rng) => (0..nb_nodes)
+				.flat_map(|_| from_f32(util::sample_unit_nsphere(rng, dimensions)))
+				.collect(),
+			#[cfg(feature = "rand")]
+			InitialPlacement::Ball(rng) => (0..nb_nodes)
+				.flat_map(|_| from_f32(util::sample_unit_nball(rng, dimensions)))
+				.collect(),
+			InitialPlacement::Circle => (0..nb_nodes)
+				.flat_map(|n| from_f32(circle_point(n, nb_nodes, 1.0, dimensions)))
+				.collect(),
+			InitialPlacement::Halton { seed } => {
+				let bases = primes(dimensions);
+				(0..nb_nodes)
+					.flat_map(|n| {
+						bases.iter().map(move |base| {
+							let i = seed.wrapping_add(n as u64).wrapping_add(1);
+							T::from(2.0 * radical_inverse(i, *base as u64) - 1.0)
+						})
+					})
+					.collect()
+			}
+			InitialPlacement::Grid => {
+				// Smallest side such that side^dimensions >= nb_nodes
+				let mut side = 1usize;
+				while (side as f64).powi(dimensions as i32) < nb_nodes as f64 {
+					side += 1;
+				}
+				(0..nb_nodes)
+					.flat_map(|n| {
+						let mut rest = n;
+						from_f32(
+							(0..dimensions)
+								.map(|_| {
+									let i = rest % side;
+									rest /= side;
+									if side == 1 {
+										0.0
+									} else {
+										2.0 * i as f32 / (side - 1) as f32 - 1.0
+									}
+								})
+								.collect(),
+						)
+					})
+					.collect()
+			}
+			InitialPlacement::Radial { root } => {
+				if root >= nb_nodes {
+					return Err(Error::NodeIndex {
+						edge: None,
+						node: root,
+						nb_nodes,
+					});
+				}
+				let depths = bfs_depths(&neighbor_lists(nb_nodes, edges), root);
+				let max_depth = depths.iter().filter_map(|d| *d).max().unwrap_or(0);
+				let depths: Vec<usize> = depths
+					.into_iter()
+					.map(|d| d.unwrap_or(max_depth + 1))
+					.collect();
+				let mut ring_sizes = vec![0usize; max_depth + 2];
+				for d in depths.iter() {
+					ring_sizes[*d] += 1;
+				}
+				let mut ring_counts = vec![0usize; max_depth + 2];
+				depths
+					.into_iter()
+					.flat_map(|d| {
+						let i = ring_counts[d];
+						ring_counts[d] += 1;
+						from_f32(circle_point(i, ring_sizes[d], d as f32, dimensions))
+					})
+					.collect()
+			}
+			InitialPlacement::Spectral => {
+				let axes = spectral_axes(nb_nodes, edges, dimensions);
+				(0..nb_nodes)
+					.flat_map(|n| axes.iter().map(move |axis| T::from(axis[n] as f32)))
+					.collect()
+			}
+			InitialPlacement::PivotMds { pivots } => {
+				let axes = pivot_mds_axes(nb_nodes, edges, dimensions, pivots);
+				(0..nb_nodes)
+					.flat_map(|n| axes.iter().map(move |axis| T::from(axis[n] as f32)))
+					.collect()
+			}
+			InitialPlacement::Custom(mut f) => {
+				let mut points: Vec<T> = (0..nb_nodes * dimensions).map(|_| T::zero()).collect();
+				for (n, pos) in points.chunks_mut(dimensions).enumerate() {
+					f(n, pos);
+				}
+				points
+			}
+		};
+		Ok(points)
+	}
+}
+
+/// `i`-th of `count` points evenly spaced on the circle of the given radius
+///
+/// In 1D, points are evenly spaced on the segment `[-radius, radius]`.
+fn circle_point(i: usize, count: usize, radius: f32, dimensions: usize) -> Vec<f32> {
+	let mut pos = vec![0.0; dimensions];
+	if dimensions == 1 {
+		if count > 1 {
+			pos[0] = radius * (2.0 * i as f32 / (count - 1) as f32 - 1.0);
+		}
+	} else {
+		let angle = std::f32::consts::TAU * i as f32 / count as f32;
+		pos[0] = radius * angle.cos();
+		pos[1] = radius * angle.sin();
+	}
+	pos
+}
+
+/// The first `count` prime numbers
+fn primes(count: usize) -> Vec<usize> {
+	let mut primes: Vec<usize> = Vec::with_capacity(count);
+	let mut candidate = 2;
+	while primes.len() < count {
+		if primes.iter().all(|p| candidate % p != 0) {
+			primes.push(candidate);
+		}
+		candidate += 1;
+	}
+	primes
+}
+
+/// Van der Corput sequence: digits of `i` in `base`, mirrored after the radix point
+fn radical_inverse(mut i: u64, base: u64) -> f32 {
+	let mut result = 0.0f64;
+	let mut f = 1.0 / base as f64;
+	while i > 0 {
+		result += (i % base) as f64 * f;
+		i /= base;
+		f /= base as f64;
+	}
+	result as f32
+}
+
+/// Maximum number of power iterations for each eigenvector
+const EIGEN_MAX_ITERATIONS: usize = 1000;
+/// Precision of the eigenvectors
+const EIGEN_TOLERANCE: f64 = 1e-7;
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+	a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+}
+
+/// Scales `v` to unit norm, returning false if it is null
+fn normalize(v: &mut [f64]) -> bool {
+	let norm = dot(v, v).sqrt();
+	if norm < 1e-12 {
+		return false;
+	}
+	v.iter_mut().for_each(|x| *x /= norm);
+	true
+}
+
+/// Removes the components of `v` along the orthonormal vectors of `basis`
+fn orthogonalize(v: &mut [f64], basis: &[Vec<f64>]) {
+	for u in basis.iter() {
+		let p = dot(v, u);
+		v.iter_mut().zip(u.iter()).for_each(|(x, y)| *x -= p * y);
+	}
+}
+
+/// Unit eigenvector of the largest eigenvalue of the symmetric positive semi-definite matrix `multiply`,
+/// orthogonal to `basis`, by power iteration
+///
+/// The start vector is deterministic, depending on `seed`. Returns `None` if the matrix is null orthogonally to `basis`.
+fn leading_eigenvector<F: Fn(&[f64]) -> Vec<f64>>(
+	size: usize,
+	basis: &[Vec<f64>],
+	seed: usize,
+	multiply: F,
+) -> Option<Vec<f64>> {
+	let mut v: Vec<f64> = (0..size)
+		.map(|i| ((i as f64 + 1.0) * 12.9898 + (seed as f64 + 1.0) * 78.233).sin())
+		.collect();
+	orthogonalize(&mut v, basis);
+	if !normalize(&mut v) {
+		return None;
+	}
+	for _ in 0..EIGEN_MAX_ITERATIONS {
+		let mut w = multiply(&v);
+		orthogonalize(&mut w, basis);
+		if !normalize(&mut w) {
+			return None;
+		}
+		let delta: f64 = w.iter().zip(v.iter()).map(|(x, y)| (x - y).powi(2)).sum();
+		v = w;
+		if delta < EIGEN_TOLERANCE.powi(2) {
+			break;
+		}
+	}
+	Some(v)
+}
+
+/// Divides all the coordinates by the largest absolute one, so that they are in `[-1, 1]`
+fn scale_to_unit(axes: &mut [Vec<f64>]) {
+	let max = axes
+		.iter()
+		.flatten()
+		.fold(0.0f64, |max, x| max.max(x.abs()));
+	if max > 0.0 {
+		axes.iter_mut().flatten().for_each(|x| *x /= max);
+	}
+}
+
+/// Small deterministic offset in `[-1, 1]` for the coordinate `d` of the node `n`
+pub(crate) fn tie_break(n: usize, d: usize) -> f64 {
+	((n as f64 + 1.0) * 12.9898 + (d as f64 + 1.0) * 78.233).sin()
+}
+
+/// Shifts the nodes by a hundredth of each axis' extent (see [`tie_break`]),
+/// so that structurally equivalent nodes (e.g. leaves of the same node) are not superposed
+fn separate(axes: &mut [Vec<f64>]) {
+	for (d, axis) in axes.iter_mut().enumerate() {
+		let (min, max) = axis
+			.iter()
+			.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+				(min.min(*x), max.max(*x))
+			});
+		let extent = if max > min { max - min } else { 1.0 };
+		for (n, x) in axis.iter_mut().enumerate() {
+			*x += 0.01 * extent * tie_break(n, d);
+		}
+	}
+}
+
+/// `dimensions` leading non-trivial eigenvectors of `D^-1/2 A D^-1/2` (i.e. of the normalized Laplacian), as node coordinates
+///
+/// `A` is the adjacency matrix (undirected, without self-loops) and `D` the degree matrix.
+/// Power iteration is done on `(I + D^-1/2 A D^-1/2) / 2` (whose eigenvalues are non-negative),
+/// orthogonally to the trivial eigenvector `D^1/2 1` and to the previous eigenvectors.
+/// Coordinates are then `D^-1/2 v`, separated (see [`separate`]) and normalized to `[-1, 1]` on each axis.
+fn spectral_axes(nb_nodes: usize, edges: &[Edge], dimensions: usize) -> Vec<Vec<f64>> {
+	let edges: Vec<Edge> = edges
+		.iter()
+		.copied()
+		.filter(|(n1, n2)| n1 != n2 && *n1 < nb_nodes && *n2 < nb_nodes)
+		.collect();
+	let mut degrees = vec![0.0f64; nb_nodes];
+	for (n1, n2) in edges.iter() {
+		degrees[*n1] += 1.0;
+		degrees[*n2] += 1.0;
+	}
+	let sqrt_degrees: Vec<f64> = degrees.iter().map(|d| d.max(1.0).sqrt()).collect();
+	let multiply = |v: &[f64]| {
+		let mut w: Vec<f64> = v.iter().map(|x| x / 2.0).collect();
+		for (n1, n2) in edges.iter() {
+			let f = 0.5 / (sqrt_degrees[*n1] * sqrt_degrees[*n2]);
+			w[*n1] += f * v[*n2];
+			w[*n2] += f * v[*n1];
+		}
+		w
+	};
+
+	let mut trivial: Vec<f64> = degrees.iter().map(|d| d.sqrt()).collect();
+	let mut basis = Vec::new();
+	if normalize(&mut trivial) {
+		basis.push(trivial);
+	}
+	let mut axes = Vec::with_capacity(dimensions);
+	for axis in 0..dimensions {
+		match leading_eigenvector(nb_nodes, &basis, axis, multiply) {
+			Some(v) => {
+				axes.push(
+					v.iter()
+						.zip(sqrt_degrees.iter())
+						.map(|(x, d)| x / d)
+						.collect(),
+				);
+				basis.push(v);
+			}
+			// Fewer nodes than dimensions
+			None => axes.push(vec![0.0; nb_nodes]),
+		}
+	}
+	separate(&mut axes);
+	for axis in axes.iter_mut() {
+		scale_to_unit(std::slice::from_mut(axis));
+	}
+	axes
+}
+
+/// Pivot MDS coordinates (Brandes & Pich, 2006) on `dimensions` axes, using at most `pivots` pivots
+///
+/// Pivots are chosen by max-min distance, starting from node `0`. Graph distances (ignoring edge directions) from the pivots
+/// are squared and double-centered into an `nb_nodes * pivots` matrix `C`, and the coordinates are `C v`
+/// for the leading eigenvectors `v` of `C^T C`. Unreachable nodes are one hop further than the farthest reachable node.
+/// Nodes are separated (see [`separate`]), then all the axes are scaled by the same factor to fit in `[-1, 1]`, keeping the proportions.
+fn pivot_mds_axes(
+	nb_nodes: usize,
+	edges: &[Edge],
+	dimensions: usize,
+	pivots: usize,
+) -> Vec<Vec<f64>> {
+	if nb_nodes == 0 {
+		return vec![Vec::new(); dimensions];
+	}
+	let neighbors = neighbor_lists(nb_nodes, edges);
+	// `columns[j][n]` is the squared distance between the pivot `j` and the node `n`
+	let mut columns: Vec<Vec<f64>> = Vec::new();
+	let mut min_depths = vec![usize::MAX; nb_nodes];
+	let mut pivot = 0;
+	while columns.len() < pivots.max(1) {
+		let depths = bfs_depths(&neighbors, pivot);
+		let max_depth = depths.iter().filter_map(|d| *d).max().unwrap_or(0);
+		let depths: Vec<usize> = depths
+			.into_iter()
+			.map(|d| d.unwrap_or(max_depth + 1))
+			.collect();
+		columns.push(depths.iter().map(|d| (*d as f64).powi(2)).collect());
+		for (min, d) in min_depths.iter_mut().zip(depths) {
+			*min = (*min).min(d);
+		}
+		pivot = (0..nb_nodes).fold(0, |p, n| if min_depths[n] > min_depths[p] { n } else { p });
+		// Every node is a pivot
+		if min_depths[pivot] == 0 {
+			break;
+		}
+	}
+
+	// Double centering
+	let nb_pivots = columns.len();
+	let column_means: Vec<f64> = columns
+		.iter()
+		.map(|column| column.iter().sum::<f64>() / nb_nodes as f64)
+		.collect();
+	let row_means: Vec<f64> = (0..nb_nodes)
+		.map(|n| columns.iter().map(|column| column[n]).sum::<f64>() / nb_pivots as f64)
+		.collect();
+	let mean = column_means.iter().sum::<f64>() / nb_pivots as f64;
+	for (column, column_mean) in columns.iter_mut().zip(column_means.iter()) {
+		for (x, row_mean) in column.iter_mut().zip(row_means.iter()) {
+			*x = -0.5 * (*x - row_mean - column_mean + mean);
+		}
+	}
+
+	let c_mul = |v: &[f64]| -> Vec<f64> {
+		let mut w = vec![0.0; nb_nodes];
+		for (column, x) in columns.iter().zip(v.iter()) {
+			w.iter_mut()
+				.zip(column.iter())
+				.for_each(|(y, c)| *y += c * x);
+		}
+		w
+	};
+	let mut basis = Vec::new();
+	let mut axes = Vec::with_capacity(dimensions);
+	for axis in 0..dimensions {
+		let eigenvector = leading_eigenvector(nb_pivots, &basis, axis, |v| {
+			let w = c_mul(v);
+			columns.iter().map(|column| dot(column, &w)).collect()
+		});
+		match eigenvector {
+			Some(v) => {
+				axes.push(c_mul(&v));
+				basis.push(v);
+			}
+			// Fewer pivots than dimensions
+			None => axes.push(vec![0.0; nb_nodes]),
+		}
+	}
+	separate(&mut axes);
+	scale_to_unit(&mut axes);
+	axes
+}
+
+/// Undirected adjacency lists, ignoring edges referring to missing nodes
+pub(crate) fn neighbor_lists(nb_nodes: usize, edges: &[Edge]) -> Vec<Vec<usize>> {
+	let mut neighbors: Vec<Vec<usize>> = (0..nb_nodes).map(|_| Vec::new()).collect();
+	for (n1, n2) in edges.iter() {
+		if *n1 < nb_nodes && *n2 < nb_nodes {
+			neighbors[*n1].push(*n2);
+			neighbors[*n2].push(*n1);
+		}
+	}
+	neighbors
+}
+
+/// Distance of each node from `root` (`None` if unreachable) in the graph of adjacency lists `neighbors`
+fn bfs_depths(neighbors: &[Vec<usize>], root: usize) -> Vec<Option<usize>> {
+	let mut depths = vec![None; neighbors.len()];
+	depths[root] = Some(0);
+	let mut queue = VecDeque::from(vec![root]);
+	while let Some(n) = queue.pop_front() {
+		let depth = depths[n].map(|d| d + 1);
+		for m in neighbors[n].iter() {
+			if depths[*m].is_none() {
+				depths[*m] = depth;
+				queue.push_back(*m);
+			}
+		}
+	}
+	depths
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn norms(points: &[f64], dimensions: usize) -> Vec<f64> {
+		points
+			.chunks(dimensions)
+			.map(|pos| pos.iter().map(|x| x * x).sum::<f64>().sqrt())
+			.collect()
+	}
+
+	#[cfg(feature = "rand")]
+	#[test]
+	fn test_random_placements() {
+		let mut rng = rand::thread_rng();
+		let points = InitialPlacement::<f64>::Sphere(&mut rng)
+			.place(50, &[], 4)
+			.unwrap();
+		assert!(norms(&points, 4).iter().all(|d| (d - 1.0).abs() < 1e-5));
+		let points = InitialPlacement::<f64>::Ball(&mut rng)
+			.place(50, &[], 3)
+			.unwrap();
+		assert!(norms(&points, 3).iter().all(|d| *d <= 1.0 + 1e-5));
+		let points = InitialPlacement::<f64>::Cube(&mut rng)
+			.place(50, &[], 2)
+			.unwrap();
+		assert!(points.iter().all(|x| x.abs() <= 1.0));
+	}
+
+	#[test]
+	fn test_placements() {
+		let points = InitialPlacement::<f64>::Circle.place(4, &[], 3).unwrap();
+		assert!(norms(&points, 3).iter().all(|d| (d - 1.0).abs() < 1e-6));
+		assert!(points.chunks(3).all(|pos| pos[2] == 0.0));
+
+		let points = InitialPlacement::<f64>::Grid.place(5, &[], 2).unwrap();
+		assert_eq!(
+			points,
+			vec![-1.0, -1.0, 0.0, -1.0, 1.0, -1.0, -1.0, 0.0, 0.0, 0.0]
+		);
+
+		// 0 - 1 - 2, 0 - 3, 4 unreachable
+		let edges = [(1, 0), (1, 2), (3, 0)];
+		let points: Vec<f64> = InitialPlacement::Radial { root: 0 }
+			.place(5, &edges, 2)
+			.unwrap();
+		let norms = norms(&points, 2);
+		for (d, expected) in norms.iter().zip([0.0, 1.0, 2.0, 1.0, 3.0].iter()) {
+			assert!((d - expected).abs() < 1e-6);
+		}
+		// Nodes of the same ring do not overlap
+		assert!((points[2] - points[6]).abs() + (points[3] - points[7]).abs() > 1.0);
+		let placement: InitialPlacement<f64> = InitialPlacement::Radial { root: 5 };
+		assert_eq!(
+			placement.place(5, &edges, 2).err(),
+			Some(Error::NodeIndex {
+				edge: None,
+				node: 5,
+				nb_nodes: 5
+			})
+		);
+
+		let placement: InitialPlacement<f64> = InitialPlacement::Halton { seed: 0 };
+		let points = placement.place(100, &[], 3).unwrap();
+		for (x, y) in points
+			.iter()
+			.zip([0.0, -1.0 / 3.0, -0.6, -0.5, 1.0 / 3.0, -0.2].iter())
+		{
+			assert!((x - y).abs() < 1e-6);
+		}
+		assert!(points.iter().all(|x| *x >= -1.0 && *x < 1.0));
+		// Every octant is filled evenly
+		let mut octants = [0; 8];
+		for pos in points.chunks(3) {
+			octants[pos
+				.iter()
+				.enumerate()
+				.map(|(i, x)| if *x < 0.0 { 0 } else { 1 << i })
+				.sum::<usize>()] += 1;
+		}
+		assert!(
+			octants.iter().all(|count| *count >= 10 && *count <= 15),
+			"{:?}",
+			octants
+		);
+		let placement: InitialPlacement<f64> = InitialPlacement::Halton { seed: 2 };
+		assert_eq!(placement.place(2, &[], 3).unwrap(), points[6..12]);
+		// The sequence wraps around
+		let placement: InitialPlacement<f64> = InitialPlacement::Halton { seed: u64::MAX };
+		let wrapped = placement.place(3, &[], 3).unwrap();
+		assert_eq!(wrapped[..3], [-1.0, -1.0, -1.0]);
+		assert_eq!(wrapped[3..], points[..6]);
+
+		// Spectral placement of a path is monotonic along the first axis
+		let edges: Vec<Edge> = (0..9).map(|n| (n, n + 1)).collect();
+		let points = InitialPlacement::<f64>::Spectral
+			.place(10, &edges, 2)
+			.unwrap();
+		let xs: Vec<f64> = points.chunks(2).map(|pos| pos[0]).collect();
+		assert!(
+			xs.windows(2).all(|x| x[0] < x[1]) || xs.windows(2).all(|x| x[0] > x[1]),
+			"{:?}",
+			xs
+		);
+		assert!(points.iter().all(|x| x.abs() <= 1.0));
+		assert!(points.chunks(2).any(|pos| pos[1].abs() > 0.5));
+		// Twin leaves (20 and 21 on node 5) are not superposed
+		let mut edges: Vec<Edge> = (0..19).map(|n| (n, n + 1)).collect();
+		edges.extend_from_slice(&[(5, 20), (5, 21)]);
+		let points = InitialPlacement::<f64>::Spectral
+			.place(22, &edges, 2)
+			.unwrap();
+		let gap = (points[40] - points[42]).abs() + (points[41] - points[43]).abs();
+		assert!(gap > 1e-3, "{:?}", &points[40..]);
+
+		// Pivot MDS of a grid keeps its shape
+		let mut edges = Vec::new();
+		for i in 0..6 {
+			for j in 0..6 {
+				if i < 5 {
+					edges.push((i * 6 + j, (i + 1) * 6 + j));
+				}
+				if j < 5 {
+					edges.push((i * 6 + j, i * 6 + j + 1));
+				}
+			}
+		}
+		let placement: InitialPlacement<f64> = InitialPlacement::PivotMds { pivots: 10 };
+		let points = placement.place(36, &edges, 2).unwrap();
+		let dist = |n1: usize, n2: usize| {
+			let (p1, p2) = (&points[n1 * 2..n1 * 2 + 2], &points[n2 * 2..n2 * 2 + 2]);
+			((p1[0] - p2[0]).powi(2) + (p1[1] - p2[1]).powi(2)).sqrt()
+		};
+		// Opposite corners are the farthest, sides are roughly equal
+		assert!(dist(0, 35) > dist(0, 5) * 1.3);
+		assert!((dist(0, 5) / dist(0, 30) - 1.0).abs() < 0.1);
+		assert!((dist(0, 35) / dist(5, 30) - 1.0).abs() < 0.1);
+		assert!(points.iter().all(|x| x.abs() <= 1.0 + 1e-9));
+		// Leaves of a star are not superposed
+		let edges: Vec<Edge> = (1..8).map(|n| (0, n)).collect();
+		let placement: InitialPlacement<f64> = InitialPlacement::PivotMds { pivots: 4 };
+		let points = placement.place(8, &edges, 2).unwrap();
+		for n1 in 0..8 {
+			for n2 in n1 + 1..8 {
+				let gap = (points[n1 * 2] - points[n2 * 2]).abs()
+					+ (points[n1 * 2 + 1] - points[n2 * 2 + 1]).abs();
+				assert!(gap > 1e-3, "{} {} {:?}", n1, n2, points);
+			}
+		}
+		// More dimensions than pivots
+		let placement: InitialPlacement<f64> = InitialPlacement::PivotMds { pivots: 3 };
+		let points = placement.place(3, &[], 4).unwrap();
+		assert!(points.iter().all(|x| x.is_finite()));
+
+		let points = InitialPlacement::<f64>::Custom(Box::new(|n, pos| pos[1] = n as f64))
+			.place(3, &[], 2)
+			.unwrap();
+		assert_eq!(points, vec![0.0, 0.0, 0.0, 1.0, 0.0, 2.0]);
+	}
+}
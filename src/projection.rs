@@ -0,0 +1,265 @@
+use crate::{Coord, Error, Layout, PointList};
+
+/// Maximum number of power iterations for each principal axis
+const PCA_ITERATIONS: usize = 1000;
+
+impl<T: Coord + std::fmt::Debug> Layout<T> {
+	/// Projects the layout on its `dimensions` principal axes (PCA), so that it keeps as much of its shape as possible
+	///
+	/// Positions and speeds are expressed in the new axes, and `settings.dimensions` is updated, so that iterations can continue.
+	/// The first coordinate is along the axis of largest variance.
+	/// Fails if `dimensions` is zero or greater than `settings.dimensions`.
+	pub fn project(&mut self, dimensions: usize) -> Result<(), Error> {
+		if dimensions == 0 {
+			return Err(Error::ZeroDimensions);
+		}
+		if dimensions > self.settings.dimensions {
+			return Err(Error::DimensionChange {
+				current: self.settings.dimensions,
+				requested: dimensions,
+			});
+		}
+		let axes = principal_axes(&self.points, dimensions);
+		let project = |points: &PointList<T>| PointList {
+			dimensions,
+			points: points
+				.iter()
+				.flat_map(|pos| axes.iter().map(move |axis| dot(pos, axis)))
+				.collect(),
+		};
+		self.points = project(&self.points);
+		self.speeds = project(&self.speeds);
+		self.old_speeds = project(&self.old_speeds);
+		self.settings.dimensions = dimensions;
+		Ok(())
+	}
+
+	/// Adds dimensions to the layout, with small random coordinates (a hundredth of the layout's radius)
+	///
+	/// Existing coordinates are kept, new speeds are zero, and `settings.dimensions` is updated, so that iterations can continue.
+	/// Fails if `dimensions` is less than `settings.dimensions`.
+	#[cfg(feature = "rand")]
+	pub fn lift<R: rand::Rng>(&mut self, dimensions: usize, rng: &mut R) -> Result<(), Error>
+	where
+		rand::distributions::Standard: rand::distributions::Distribution<T>,
+		T: rand::distributions::uniform::SampleUniform,
+	{
+		if dimensions < self.settings.dimensions {
+			return Err(Error::DimensionChange {
+				current: self.settings.dimensions,
+				requested: dimensions,
+			});
+		}
+		let added = dimensions - self.settings.dimensions;
+		// Root mean square distance to the centroid
+		let centroid = mean(&self.points);
+		let mut radius = T::zero();
+		for pos in self.points.iter() {
+			for (x, c) in pos.iter().zip(centroid.iter()) {
+				radius += (x.clone() - c.clone()).powi(2);
+			}
+		}
+		radius = (radius / T::from(self.nodes.len() as f32)).sqrt();
+		if !radius.positive() {
+			radius = T::one();
+		}
+		let offset = radius / T::from(100.0);
+
+		let mut points = Vec::with_capacity(self.nodes.len() * dimensions);
+		for pos in self.points.iter() {
+			points.extend_from_slice(pos);
+			points.extend(
+				crate::util::sample_unit_ncube::<T, _>(rng, added)
+					.into_iter()
+					.map(|x| x * offset.clone()),
+			);
+		}
+		let lift = |points: &PointList<T>| PointList {
+			dimensions,
+			points: points
+				.iter()
+				.flat_map(|pos| {
+					pos.iter()
+						.cloned()
+						.chain((0..added).map(|_| T::zero()))
+						.collect::<Vec<T>>()
+				})
+				.collect(),
+		};
+		self.speeds = lift(&self.speeds);
+		self.old_speeds = lift(&self.old_speeds);
+		self.points = PointList { dimensions, points };
+		self.settings.dimensions = dimensions;
+		Ok(())
+	}
+}
+
+fn dot<T: Coord>(a: &[T], b: &[T]) -> T {
+	let mut sum = T::zero();
+	for (x, y) in a.iter().zip(b.iter()) {
+		sum += x.clone() * y.clone();
+	}
+	sum
+}
+
+fn mean<T: Coord>(points: &PointList<T>) -> Vec<T> {
+	let mut mean: Vec<T> = (0..points.dimensions).map(|_| T::zero()).collect();
+	let mut count = 0usize;
+	for pos in points.iter() {
+		for (m, x) in mean.iter_mut().zip(pos.iter()) {
+			*m += x.clone();
+		}
+		count += 1;
+	}
+	if count > 0 {
+		let count = T::from(count as f32);
+		mean.iter_mut().for_each(|m| *m /= count.clone());
+	}
+	mean
+}
+
+/// Removes from `v` its components along the orthonormal vectors `basis`, then normalizes it (returns false if it is null)
+fn orthonormalize<T: Coord>(v: &mut [T], basis: &[Vec<T>]) -> bool {
+	for u in basis.iter() {
+		let p = dot(v, u);
+		v.iter_mut()
+			.zip(u.iter())
+			.for_each(|(x, y)| *x -= p.clone() * y.clone());
+	}
+	let norm = dot(v, v).sqrt();
+	if !norm.positive() {
+		return false;
+	}
+	v.iter_mut().for_each(|x| *x /= norm.clone());
+	true
+}
+
+/// `count` orthonormal principal axes of `points`, by decreasing variance
+///
+/// Computed by power iteration on the covariance matrix, orthogonally to the previous axes.
+/// If the points span fewer dimensions, the remaining axes are completed with any orthogonal directions.
+fn principal_axes<T: Coord>(points: &PointList<T>, count: usize) -> Vec<Vec<T>> {
+	let dimensions = points.dimensions;
+	let mean = mean(points);
+	let mut covariance: Vec<Vec<T>> = (0..dimensions)
+		.map(|_| (0..dimensions).map(|_| T::zero()).collect())
+		.collect();
+	for pos in points.iter() {
+		for i in 0..dimensions {
+			let xi = pos[i].clone() - mean[i].clone();
+			for j in 0..dimensions {
+				covariance[i][j] += xi.clone() * (pos[j].clone() - mean[j].clone());
+			}
+		}
+	}
+
+	let mut axes: Vec<Vec<T>> = Vec::with_capacity(count);
+	for axis in 0..count {
+		// Deterministic start, not orthogonal to the coordinate axes
+		let mut v: Vec<T> = (0..dimensions)
+			.map(|i| T::from(if i == axis { 2.0 } else { 1.0 }))
+			.collect();
+		let mut found = orthonormalize(&mut v, &axes);
+		for _ in 0..PCA_ITERATIONS {
+			if !found {
+				break;
+			}
+			let mut w: Vec<T> = covariance.iter().map(|row| dot(row, &v)).collect();
+			found = orthonormalize(&mut w, &axes);
+			if found {
+				v = w;
+			}
+		}
+		if !found {
+			// Null variance: any orthogonal direction
+			for i in 0..dimensions {
+				v = (0..dimensions)
+					.map(|j| if i == j { T::one() } else { T::zero() })
+					.collect();
+				if orthonormalize(&mut v, &axes) {
+					break;
+				}
+			}
+		}
+		axes.push(v);
+	}
+	axes
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Settings;
+
+	#[test]
+	fn test_projection() {
+		// Points on the plane z = x, stretched along y
+		let positions: Vec<Vec<f64>> = (0..20)
+			.map(|n| {
+				let (x, y) = ((n % 4) as f64, (n / 4) as f64 * 3.0);
+				vec![x, y, x]
+			})
+			.collect();
+		let edges: Vec<(usize, usize)> = (0..19).map(|n| (n, n + 1)).collect();
+		let mut layout = Layout::<f64>::from_position_graph(
+			edges,
+			positions.iter().map(|pos| &pos[..]),
+			Settings {
+				dimensions: 3,
+				..Default::default()
+			},
+		)
+		.unwrap();
+		layout.iteration();
+		let before: Vec<Vec<f64>> = layout.points.iter().map(|pos| pos.to_vec()).collect();
+		let dist = |a: &[f64], b: &[f64]| {
+			a.iter()
+				.zip(b.iter())
+				.map(|(x, y)| (x - y).powi(2))
+				.sum::<f64>()
+				.sqrt()
+		};
+
+		assert_eq!(
+			layout.project(4),
+			Err(Error::DimensionChange {
+				current: 3,
+				requested: 4
+			})
+		);
+		assert_eq!(layout.project(0), Err(Error::ZeroDimensions));
+		layout.project(2).unwrap();
+		assert_eq!(layout.settings.dimensions, 2);
+		assert_eq!(layout.points.points.len(), 40);
+		assert_eq!(layout.speeds.points.len(), 40);
+		// The points are nearly planar, so distances are kept
+		for (n1, n2) in [(0, 19), (3, 4), (5, 12)].iter() {
+			let (d1, d2) = (
+				dist(&before[*n1], &before[*n2]),
+				dist(layout.points.get(*n1), layout.points.get(*n2)),
+			);
+			assert!((d1 - d2).abs() < 0.05 * d1, "{} {}", d1, d2);
+		}
+		layout.iteration();
+
+		#[cfg(feature = "rand")]
+		{
+			let before = layout.points.clone();
+			assert_eq!(
+				layout.lift(1, &mut rand::thread_rng()),
+				Err(Error::DimensionChange {
+					current: 2,
+					requested: 1
+				})
+			);
+			layout.lift(3, &mut rand::thread_rng()).unwrap();
+			assert_eq!(layout.settings.dimensions, 3);
+			for (pos, old) in layout.points.iter().zip(before.iter()) {
+				assert_eq!(&pos[..2], old);
+				assert!(pos[2].abs() < 1.0);
+			}
+			assert!(layout.speeds.iter().all(|speed| speed[2] == 0.0));
+			layout.iteration();
+		}
+	}
+}
@@ -1,21 +1,84 @@
-use maths_traits::{
-	algebra::group_like::{
-		additive::Sub,
-		multiplicative::{Div, DivAssign},
-	},
-	analysis::{ordered::Signed, RealExponential},
-};
+use num_traits::{One, Zero};
 #[cfg(feature = "rand")]
 use rand::Rng;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
-pub trait Coord = Clone
-	+ Div<Self, Output = Self>
-	+ DivAssign<Self>
-	+ From<u32>
+/// Number type usable for coordinates (implemented for `f32` and `f64`)
+pub trait Coord:
+	Clone
+	+ PartialOrd
+	+ Zero
+	+ One
 	+ From<f32>
-	+ Signed
-	+ RealExponential
-	+ Sub<Self>;
+	+ Add<Output = Self>
+	+ Sub<Output = Self>
+	+ Mul<Output = Self>
+	+ Div<Output = Self>
+	+ Neg<Output = Self>
+	+ AddAssign
+	+ SubAssign
+	+ MulAssign
+	+ DivAssign
+	+ Send
+	+ Sync
+{
+	fn sqrt(self) -> Self;
+	fn ln(self) -> Self;
+	/// `ln(1 + self)`, more accurate than `ln` for small values
+	fn ln_1p(self) -> Self;
+	fn exp(self) -> Self;
+	fn powi(self, n: i32) -> Self;
+	/// Neither infinite nor NaN
+	fn is_finite(&self) -> bool;
+	/// Uniform random value in `[-1, 1)`
+	#[cfg(feature = "rand")]
+	fn sample_unit<R: Rng + ?Sized>(rng: &mut R) -> Self;
+
+	/// `self > 0` (false for NaN)
+	fn positive(&self) -> bool {
+		*self > Self::zero()
+	}
+	/// `self < 0` (false for NaN)
+	fn negative(&self) -> bool {
+		*self < Self::zero()
+	}
+	/// `self <= 0` (false for NaN)
+	fn non_positive(&self) -> bool {
+		*self <= Self::zero()
+	}
+}
+
+macro_rules! impl_coord {
+	($t:ty) => {
+		impl Coord for $t {
+			fn sqrt(self) -> Self {
+				<$t>::sqrt(self)
+			}
+			fn ln(self) -> Self {
+				<$t>::ln(self)
+			}
+			fn ln_1p(self) -> Self {
+				<$t>::ln_1p(self)
+			}
+			fn exp(self) -> Self {
+				<$t>::exp(self)
+			}
+			fn powi(self, n: i32) -> Self {
+				<$t>::powi(self, n)
+			}
+			fn is_finite(&self) -> bool {
+				<$t>::is_finite(*self)
+			}
+			#[cfg(feature = "rand")]
+			fn sample_unit<R: Rng + ?Sized>(rng: &mut R) -> Self {
+				rng.gen_range(-1.0..1.0)
+			}
+		}
+	};
+}
+
+impl_coord!(f32);
+impl_coord!(f64);
 
 /// n-dimensional position
 pub type Position<T> = [T];
@@ -31,19 +94,101 @@ pub fn clone_slice_mut<'a, T: Clone>(s: &'a [T]) -> Vec<T> {
 
 pub type Edge = (usize, usize);
 
-pub struct Node {
+pub struct Node<T> {
 	pub degree: u32,
+	/// Mass used by repulsion, gravity and displacement (`degree + 1` by default)
+	pub mass: T,
+	/// Radius, used when preventing overlapping (`0` by default)
+	pub size: T,
+	/// A fixed node is never moved, but still attracts and repulses other nodes
+	pub fixed: bool,
+	/// Group (e.g. community) of the node, used by the group multipliers of `Settings`
+	pub group: Option<usize>,
 }
 
 #[inline]
 pub fn norm<T: Coord>(n: &Position<T>) -> T {
 	let mut sum = T::zero();
 	for i in n.iter() {
-		sum += i.clone().pow_n(2u32);
+		sum += i.clone().powi(2);
 	}
 	sum.sqrt()
 }
 
+/// Mean length of the edges whose ends both have a position
+///
+/// `None` if there is no such edge, or if they all have zero length.
+pub fn mean_edge_length<'a, T: Coord + 'a, F: Fn(usize) -> Option<&'a Position<T>>>(
+	edges: &[Edge],
+	position: F,
+) -> Option<T> {
+	let mut length = T::zero();
+	let mut count = 0usize;
+	for (n1, n2) in edges.iter() {
+		if let (Some(p1), Some(p2)) = (position(*n1), position(*n2)) {
+			let d: Vec<T> = p1
+				.iter()
+				.zip(p2.iter())
+				.map(|(x1, x2)| x1.clone() - x2.clone())
+				.collect();
+			length += norm(&d);
+			count += 1;
+		}
+	}
+	if count == 0 || length.is_zero() {
+		None
+	} else {
+		Some(length / T::from(count as f32))
+	}
+}
+
+#[inline]
+pub fn min<T: PartialOrd>(a: T, b: T) -> T {
+	if b < a {
+		b
+	} else {
+		a
+	}
+}
+
+#[inline]
+pub fn max<T: PartialOrd>(a: T, b: T) -> T {
+	if b > a {
+		b
+	} else {
+		a
+	}
+}
+
+/// Connected component of each node, components being numbered in order of their first node
+pub fn components(nb_nodes: usize, edges: &[Edge]) -> Vec<usize> {
+	fn find(parents: &mut [usize], mut n: usize) -> usize {
+		while parents[n] != n {
+			parents[n] = parents[parents[n]];
+			n = parents[n];
+		}
+		n
+	}
+	let mut parents: Vec<usize> = (0..nb_nodes).collect();
+	for (n1, n2) in edges.iter() {
+		let r1 = find(&mut parents, *n1);
+		let r2 = find(&mut parents, *n2);
+		parents[max(r1, r2)] = min(r1, r2);
+	}
+	let mut ids = vec![usize::MAX; nb_nodes];
+	let mut nb_components = 0;
+	(0..nb_nodes)
+		.map(|n| {
+			let root = find(&mut parents, n);
+			if ids[root] == usize::MAX {
+				ids[root] = nb_components;
+				nb_components += 1;
+			}
+			ids[root]
+		})
+		.collect()
+}
+
 /// Allocate Vec without initializing
 #[inline]
 pub fn valloc<T>(n: usize) -> Vec<T> {
@@ -118,6 +263,19 @@ impl<'a, T: Coord> PointList<T> {
 		let offset = n * self.dimensions;
 		self.points[offset..offset + self.dimensions].clone_from_slice(val);
 	}
+	/// Appends a point
+	pub fn push(&mut self, val: &Position<T>) {
+		self.points.extend_from_slice(val);
+	}
+	/// Removes a point, replacing it with the last one
+	pub fn swap_remove(&mut self, n: usize) {
+		let last = self.points.len() - self.dimensions;
+		let offset = n * self.dimensions;
+		for i in 0..self.dimensions {
+			self.points.swap(offset + i, last + i);
+		}
+		self.points.truncate(last);
+	}
 	pub fn iter(&self) -> PointIter<T> {
 		PointIter {
 			dimensions: self.dimensions,
@@ -137,47 +295,47 @@ impl<'a, T: Coord> PointList<T> {
 /// Uniform random distribution of points on a n-sphere
 ///
 /// `n` is the number of spatial dimensions (1 => two points; 2 => circle; 3 => sphere; etc.).
-// #[cfg(feature = "rand")]
-// pub fn sample_unit_nsphere<T: Clone + DivAssign<T> + RealExponential, R: Rng>(
-// 	rng: &mut R,
-// 	n: usize,
-// ) -> Vec<T>
-// where
-// 	rand::distributions::Standard: rand::distributions::Distribution<T>,
-// 	T: rand::distributions::uniform::SampleUniform + PartialOrd,
-// {
-// 	let mut v = valloc(n);
-// 	let mut d = T::zero();
-// 	for x in v.iter_mut() {
-// 		*x = rng.gen_range(T::one().neg()..T::one());
-// 		d += x.clone().pow_n(2u32);
-// 	}
-// 	d = d.sqrt();
-// 	for x in v.iter_mut() {
-// 		*x /= d.clone();
-// 	}
-// 	v
-// }
+#[cfg(feature = "rand")]
+pub fn sample_unit_nsphere<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<f32> {
+	loop {
+		// Normal distribution (Box-Muller) is isotropic
+		let mut v: Vec<f32> = (0..n)
+			.map(|_| {
+				let r = (-2.0 * (1.0 - rng.gen::<f32>()).ln()).sqrt();
+				r * (std::f32::consts::TAU * rng.gen::<f32>()).cos()
+			})
+			.collect();
+		let d = norm(&v);
+		if d > 0.0 {
+			for x in v.iter_mut() {
+				*x /= d;
+			}
+			return v;
+		}
+	}
+}
 
-/// Uniform random distribution of points in a n-cube
+/// Uniform random distribution of points in a n-ball
 ///
-/// `n` is the number of spatial dimensions (1 => segment; 2 => square; 3 => cube; etc.).
+/// `n` is the number of spatial dimensions (1 => segment; 2 => disc; 3 => ball; etc.).
 #[cfg(feature = "rand")]
-pub fn sample_unit_ncube<T: Clone + DivAssign<T> + RealExponential, R: Rng>(
-	rng: &mut R,
-	n: usize,
-) -> Vec<T>
-where
-	rand::distributions::Standard: rand::distributions::Distribution<T>,
-	T: rand::distributions::uniform::SampleUniform + PartialOrd,
-{
-	let mut v = valloc(n);
+pub fn sample_unit_nball<R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<f32> {
+	let mut v = sample_unit_nsphere(rng, n);
+	let r = rng.gen::<f32>().powf(1.0 / n as f32);
 	for x in v.iter_mut() {
-		*x = rng.gen_range(T::one().neg()..T::one());
+		*x *= r;
 	}
 	v
 }
 
+/// Uniform random distribution of points in a n-cube
+///
+/// `n` is the number of spatial dimensions (1 => segment; 2 => square; 3 => cube; etc.).
+#[cfg(feature = "rand")]
+pub fn sample_unit_ncube<T: Coord, R: Rng + ?Sized>(rng: &mut R, n: usize) -> Vec<T> {
+	(0..n).map(|_| T::sample_unit(rng)).collect()
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -191,4 +349,25 @@ mod tests {
 		assert_eq!(b.len(), 5);
 		assert_eq!(b, [1, 2, 6, 4, 5]);
 	}
+
+	#[test]
+	fn test_components() {
+		assert_eq!(
+			components(6, &[(4, 1), (2, 3), (1, 0), (5, 5)]),
+			vec![0, 0, 1, 1, 0, 2]
+		);
+	}
+
+	#[test]
+	fn test_point_list_swap_remove() {
+		let mut list = PointList {
+			dimensions: 2,
+			points: vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+		};
+		list.swap_remove(0);
+		assert_eq!(list.points, [4.0, 5.0, 2.0, 3.0]);
+		list.push(&[6.0, 7.0]);
+		list.swap_remove(2);
+		assert_eq!(list.points, [4.0, 5.0, 2.0, 3.0]);
+	}
 }